@@ -1,5 +1,10 @@
-use super::command::{batchput, checkconsistency, delete, deleterange, dump, get, put, scan};
+use super::command::{
+    batchput, cfstats, checkconsistency, count, createcf, delete, deleterange, dropcf, dump, get,
+    listcf, load, properties, put, scan, shell, stats, tui,
+};
 use crate::command::traits::Command;
+use crate::printer::OutputFormat;
+use crate::schema;
 use clap::{App, AppSettings, Arg, ArgMatches};
 
 pub fn build_cmd_args<'a>() -> ArgMatches<'a> {
@@ -32,14 +37,42 @@ pub fn build_cmd_args<'a>() -> ArgMatches<'a> {
                 .required(false)
                 .takes_value(false),
         )
+        .arg(
+            Arg::with_name("read_only")
+                .long("read-only")
+                .help("Opens DB in read-only mode, works while DB is used by another process")
+                .required(false)
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("secondary")
+                .long("secondary")
+                .value_name("SCRATCH_DIR")
+                .help("Opens DB as a secondary instance storing its own files in SCRATCH_DIR")
+                .required(false)
+                .takes_value(true)
+                .conflicts_with("read_only"),
+        )
         .arg(
             Arg::with_name("column_family")
                 .long("column_family")
-                .help("Name of the column family to operate on")
+                .help("Name of the column family to operate on, can be given several times")
                 .required(false)
                 .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
                 .default_value(rocksdb::DEFAULT_COLUMN_FAMILY_NAME),
         )
+        .arg(
+            Arg::with_name("output_format")
+                .long("output-format")
+                .help("Format of the printed keys and values")
+                .required(false)
+                .takes_value(true)
+                .possible_values(OutputFormat::VARIANTS)
+                .default_value("plain"),
+        )
+        .arg(schema::schema_arg())
         .subcommand(put::Put::args())
         .subcommand(get::Get::args())
         .subcommand(delete::Delete::args())
@@ -48,5 +81,15 @@ pub fn build_cmd_args<'a>() -> ArgMatches<'a> {
         .subcommand(deleterange::DeleteRange::args())
         .subcommand(checkconsistency::CheckConsistency::args())
         .subcommand(dump::Dump::args())
+        .subcommand(load::Load::args())
+        .subcommand(listcf::ListCf::args())
+        .subcommand(createcf::CreateCf::args())
+        .subcommand(dropcf::DropCf::args())
+        .subcommand(cfstats::CfStats::args())
+        .subcommand(count::Count::args())
+        .subcommand(stats::Stats::args())
+        .subcommand(properties::Properties::args())
+        .subcommand(shell::Shell::args())
+        .subcommand(tui::Tui::args())
         .get_matches()
 }
@@ -1,5 +1,7 @@
+use crate::command::database;
 use crate::command::traits::Command;
-use crate::utils;
+use crate::printer::Encoding;
+use crate::utils::codec;
 use clap::{App, Arg, ArgMatches, SubCommand};
 use rocksdb::{WriteBatch, DB};
 use simple_error::SimpleError;
@@ -9,34 +11,29 @@ use std::error::Error;
 #[derive(Debug)]
 pub struct BatchPut<'a> {
     db: DB,
+    column_family: &'a str,
     key_values: Vec<&'a str>,
-    key_hex: bool,
-    value_hex: bool,
+    encoding: Encoding,
 }
 
 impl<'a> BatchPut<'a> {
     pub fn create(matches: &'a ArgMatches<'a>) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
-        let opts = Self::build_options(matches);
-        let db = DB::open_cf(
-            &opts,
-            matches.value_of("db").unwrap(),
-            &[matches.value_of("column_family").unwrap()],
-        )?;
+        let mut opts = Self::build_options(matches);
+        let db = Self::open_db(matches, &mut opts)?;
         let subcommand_matches = matches.subcommand_matches(Self::name()).unwrap();
         let kv_str: Vec<_> = subcommand_matches.values_of("KEY-VALUE").unwrap().collect();
         Ok(std::boxed::Box::new(BatchPut {
             db,
+            column_family: database::column_family(matches, Self::name())?,
             key_values: kv_str,
-            key_hex: subcommand_matches.is_present("key_hex")
-                || subcommand_matches.is_present("hex"),
-            value_hex: subcommand_matches.is_present("value_hex")
-                || subcommand_matches.is_present("hex"),
+            encoding: Encoding::from_matches(matches, subcommand_matches)?,
         }))
     }
 }
 
 impl<'a> Command for BatchPut<'a> {
     fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        let cf = database::cf_handle(&self.db, self.column_family)?;
         let mut batch = WriteBatch::default();
         if self.key_values.len() % 2 != 0 {
             return Err(Box::new(SimpleError::new(format!(
@@ -45,17 +42,14 @@ impl<'a> Command for BatchPut<'a> {
             ))));
         }
         for i in (0..self.key_values.len()).step_by(2) {
-            let k = if self.key_hex {
-                utils::hex::decode(self.key_values[i])?
-            } else {
-                self.key_values[i].as_bytes().into()
-            };
-            let v = if self.value_hex {
-                utils::hex::decode(self.key_values[i + 1])?
-            } else {
-                self.key_values[i + 1].as_bytes().into()
-            };
-            batch.put(k, v);
+            let column_family = Some(self.column_family);
+            let k = self
+                .encoding
+                .decode_key(column_family, self.key_values[i])?;
+            let v = self
+                .encoding
+                .decode_value(column_family, &k, self.key_values[i + 1])?;
+            batch.put_cf(cf, k, v);
         }
         self.db.write(batch)?;
         println!("OK");
@@ -65,27 +59,9 @@ impl<'a> Command for BatchPut<'a> {
     fn args() -> App<'static, 'static> {
         SubCommand::with_name(Self::name())
             .about("Puts given key-value(s) to the DB")
-            .arg(
-                Arg::with_name("hex")
-                    .long("hex")
-                    .help("Key and value provided in hex format")
-                    .required(false)
-                    .takes_value(false),
-            )
-            .arg(
-                Arg::with_name("value_hex")
-                    .long("value_hex")
-                    .help("Value provided in hex format")
-                    .required(false)
-                    .takes_value(false),
-            )
-            .arg(
-                Arg::with_name("key_hex")
-                    .long("key_hex")
-                    .help("Key provided in hex format")
-                    .required(false)
-                    .takes_value(false),
-            )
+            .arg(codec::hex_arg())
+            .args(&codec::key_args())
+            .args(&codec::value_args())
             .arg(
                 Arg::with_name("KEY-VALUE")
                     .help("Key provided in hex format")
@@ -94,6 +70,10 @@ impl<'a> Command for BatchPut<'a> {
             )
     }
 
+    fn modifies_db() -> bool {
+        true
+    }
+
     fn name() -> &'static str {
         "batchput"
     }
@@ -0,0 +1,73 @@
+use crate::command::database;
+use crate::command::traits::Command;
+use crate::printer::{self, Encoding, OutputFormat};
+use clap::{App, ArgMatches, SubCommand};
+use rocksdb::DB;
+use std::boxed::Box;
+use std::error::Error;
+
+const DELIM: &str = " : ";
+
+/// Properties summarizing a column family
+const PROPERTIES: &[(&str, &str)] = &[
+    ("estimated keys", "rocksdb.estimate-num-keys"),
+    ("live data size", "rocksdb.estimate-live-data-size"),
+    ("sst files size", "rocksdb.total-sst-files-size"),
+    ("memtables size", "rocksdb.cur-size-all-mem-tables"),
+    ("level 0 files", "rocksdb.num-files-at-level0"),
+];
+
+#[derive(Debug)]
+pub struct CfStats {
+    db: DB,
+    column_families: Vec<String>,
+    output_format: OutputFormat,
+}
+
+impl CfStats {
+    pub fn create<'a>(
+        matches: &'a ArgMatches<'a>,
+    ) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
+        let mut opts = Self::build_options(matches);
+        let db = Self::open_db(matches, &mut opts)?;
+        let subcommand_matches = matches.subcommand_matches(Self::name()).unwrap();
+        let column_families = database::target_column_families(matches, subcommand_matches);
+        Ok(std::boxed::Box::new(CfStats {
+            db,
+            column_families,
+            output_format: matches.value_of("output_format").unwrap().parse()?,
+        }))
+    }
+}
+
+impl Command for CfStats {
+    fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut printer = printer::create(
+            self.output_format,
+            Box::new(std::io::stdout()),
+            Encoding::default(),
+            Some(DELIM),
+        );
+        for name in &self.column_families {
+            let cf = database::cf_handle(&self.db, name)?;
+            for (description, property) in PROPERTIES {
+                let value = match self.db.property_int_value_cf(cf, property)? {
+                    None => String::from("n/a"),
+                    Some(value) => value.to_string(),
+                };
+                printer.print(Some(name), description.as_bytes(), value.as_bytes())?;
+            }
+        }
+        printer.finish()
+    }
+
+    fn args() -> App<'static, 'static> {
+        SubCommand::with_name(Self::name())
+            .about("Prints summary of column families")
+            .arg(database::all_column_families_arg())
+    }
+
+    fn name() -> &'static str {
+        "cfstats"
+    }
+}
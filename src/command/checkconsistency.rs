@@ -1,6 +1,5 @@
 use crate::command::traits::Command;
 use clap::{App, ArgMatches, SubCommand};
-use rocksdb::DB;
 use std::boxed::Box;
 use std::error::Error;
 
@@ -12,11 +11,7 @@ impl<'a> CheckConsistency {
         let mut opts = Self::build_options(matches);
         opts.set_paranoid_checks(true);
         opts.set_num_levels(64);
-        DB::open_cf(
-            &opts,
-            matches.value_of("db").unwrap(),
-            &[matches.value_of("column_family").unwrap()],
-        )?;
+        Self::open_db(matches, &mut opts)?;
         Ok(std::boxed::Box::new(CheckConsistency {}))
     }
 }
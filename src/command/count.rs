@@ -0,0 +1,267 @@
+use crate::command::database;
+use crate::command::range::{self, KeyRange};
+use crate::command::traits::Command;
+use crate::printer::{self, Encoding, OutputFormat};
+use crate::utils::codec;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use rocksdb::{LiveFile, DB};
+use std::boxed::Box;
+use std::error::Error;
+
+const DELIM: &str = " : ";
+/// Number of keys read by `--estimate` to learn the average key and value sizes
+const SAMPLE_KEYS: usize = 1000;
+
+/// Numbers of keys and bytes in a range
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Totals {
+    keys: u64,
+    key_bytes: u64,
+    value_bytes: u64,
+}
+
+impl Totals {
+    fn add(&mut self, key: &[u8], value: &[u8]) {
+        self.keys += 1;
+        self.key_bytes += key.len() as u64;
+        self.value_bytes += value.len() as u64;
+    }
+
+    /// Totals of the given number of keys sized as the counted ones on average
+    fn scale(&self, keys: u64) -> Totals {
+        let scale = |bytes: u64| match self.keys {
+            0 => 0,
+            counted => (bytes as u128 * keys as u128 / counted as u128) as u64,
+        };
+        Totals {
+            keys,
+            key_bytes: scale(self.key_bytes),
+            value_bytes: scale(self.value_bytes),
+        }
+    }
+}
+
+/// Share of the SST data in files overlapping the range, in place of `GetApproximateSizes`
+/// which the RocksDB bindings do not expose.
+///
+/// Live file metadata does not tell the column family of a file, so the files of all the
+/// column families are weighed.
+fn range_share(files: &[LiveFile], range: &KeyRange) -> f64 {
+    let overlaps = |file: &LiveFile| {
+        range
+            .start
+            .as_deref()
+            .is_none_or(|start| file.end_key.as_deref().is_none_or(|key| key >= start))
+            && range
+                .end
+                .as_deref()
+                .is_none_or(|end| file.start_key.as_deref().is_none_or(|key| key < end))
+    };
+    let total: usize = files.iter().map(|file| file.size).sum();
+    if total == 0 {
+        // Everything is still in memtables
+        return 1.0;
+    }
+    let overlapping: usize = files
+        .iter()
+        .filter(|file| overlaps(file))
+        .map(|file| file.size)
+        .sum();
+    overlapping as f64 / total as f64
+}
+
+#[derive(Debug)]
+pub struct Count {
+    db: DB,
+    column_families: Vec<String>,
+    range: KeyRange,
+    prefix_length: Option<usize>,
+    estimate: bool,
+    /// Whether the DB has several column families, whose SST files `--estimate` can not tell apart
+    shared_files: bool,
+    output_format: OutputFormat,
+}
+
+impl Count {
+    pub fn create<'a>(
+        matches: &'a ArgMatches<'a>,
+    ) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
+        let mut opts = Self::build_options(matches);
+        let subcommand_matches = matches.subcommand_matches(Self::name()).unwrap();
+        range::set_prefix_extractor(&mut opts, subcommand_matches)?;
+        let db = Self::open_db(matches, &mut opts)?;
+        let column_families = database::target_column_families(matches, subcommand_matches);
+        let shared_files =
+            database::list_column_families(&opts, matches.value_of("db").unwrap()).len() > 1;
+        let encoding = Encoding::from_matches(matches, subcommand_matches)?;
+        Ok(std::boxed::Box::new(Count {
+            db,
+            column_families,
+            range: KeyRange::from_matches(subcommand_matches, |key| {
+                encoding.decode_key(None, key)
+            })?,
+            prefix_length: range::prefix_length(subcommand_matches)?,
+            estimate: subcommand_matches.is_present("estimate"),
+            shared_files,
+            output_format: matches.value_of("output_format").unwrap().parse()?,
+        }))
+    }
+
+    /// Counts the keys of the range by reading a sample of them, the rest is extrapolated from
+    /// the estimated number of keys of the column family. Returns whether the count is exact,
+    /// which it is if the sample covers the whole range.
+    fn estimate(&self, name: &str, files: &[LiveFile]) -> Result<(Totals, bool), Box<dyn Error>> {
+        let cf = database::cf_handle(&self.db, name)?;
+        let mut iter = self
+            .db
+            .iterator_cf_opt(
+                cf,
+                self.range.read_options(self.prefix_length, false)?,
+                self.range.mode(),
+            )
+            .take_while(|(key, _)| self.range.contains(key));
+        let mut sample = Totals::default();
+        for (key, value) in iter.by_ref().take(SAMPLE_KEYS) {
+            sample.add(&key, &value);
+        }
+        if iter.next().is_none() {
+            return Ok((sample, true));
+        }
+        let keys = self
+            .db
+            .property_int_value_cf(cf, "rocksdb.estimate-num-keys")?
+            .unwrap_or(0);
+        let keys = if self.range == KeyRange::default() {
+            keys
+        } else {
+            (keys as f64 * range_share(files, &self.range)) as u64
+        };
+        // There is at least one more key than sampled
+        Ok((sample.scale(keys.max(sample.keys + 1)), false))
+    }
+
+    fn count(&self, name: &str) -> Result<Totals, Box<dyn Error>> {
+        let cf = database::cf_handle(&self.db, name)?;
+        let iter = self.db.iterator_cf_opt(
+            cf,
+            self.range.read_options(self.prefix_length, false)?,
+            self.range.mode(),
+        );
+        let mut totals = Totals::default();
+        for (key, value) in iter.take_while(|(key, _)| self.range.contains(key)) {
+            totals.add(&key, &value);
+        }
+        Ok(totals)
+    }
+}
+
+impl Command for Count {
+    fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut printer = printer::create(
+            self.output_format,
+            Box::new(std::io::stdout()),
+            Encoding::default(),
+            Some(DELIM),
+        );
+        let files = if self.estimate {
+            if self.shared_files && self.range != KeyRange::default() {
+                eprintln!(
+                    "Warning: SST files of all column families are weighed, estimates of the range are rough"
+                );
+            }
+            self.db.live_files()?
+        } else {
+            Vec::new()
+        };
+        let show_column_family = self.column_families.len() > 1;
+        for name in &self.column_families {
+            let (totals, exact) = if self.estimate {
+                self.estimate(name, &files)?
+            } else {
+                (self.count(name)?, true)
+            };
+            let column_family = if show_column_family {
+                Some(name.as_str())
+            } else {
+                None
+            };
+            let prefix = if exact { "" } else { "estimated " };
+            for (description, value) in [
+                ("keys", totals.keys),
+                ("key bytes", totals.key_bytes),
+                ("value bytes", totals.value_bytes),
+            ] {
+                printer.print(
+                    column_family,
+                    format!("{}{}", prefix, description).as_bytes(),
+                    value.to_string().as_bytes(),
+                )?;
+            }
+        }
+        printer.finish()
+    }
+
+    fn args() -> App<'static, 'static> {
+        SubCommand::with_name(Self::name())
+            .about("Counts keys of a range along with their total key and value sizes")
+            .args(&codec::key_args())
+            .arg(database::all_column_families_arg())
+            .args(&range::args())
+            .arg(range::prefix_length_arg())
+            .arg(
+                Arg::with_name("estimate")
+                    .long("estimate")
+                    .help("Extrapolates a sample of the keys from rocksdb.estimate-num-keys and SST file sizes instead of reading the whole range")
+                    .required(false)
+                    .takes_value(false),
+            )
+    }
+
+    fn name() -> &'static str {
+        "count"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn live_file(start_key: &[u8], end_key: &[u8], size: usize) -> LiveFile {
+        LiveFile {
+            name: String::new(),
+            size,
+            level: 1,
+            start_key: Some(start_key.to_vec()),
+            end_key: Some(end_key.to_vec()),
+            num_entries: 0,
+            num_deletions: 0,
+        }
+    }
+
+    #[test]
+    fn test_scale() {
+        let mut sample = Totals::default();
+        assert_eq!(sample.scale(10).key_bytes, 0);
+        sample.add(b"ab", b"1234");
+        sample.add(b"abcd", b"");
+        assert_eq!(
+            sample.scale(10),
+            Totals {
+                keys: 10,
+                key_bytes: 30,
+                value_bytes: 20,
+            }
+        );
+    }
+
+    #[test]
+    fn test_range_share() {
+        let files = [live_file(b"a", b"c", 10), live_file(b"d", b"f", 30)];
+        assert_eq!(range_share(&files, &KeyRange::default()), 1.0);
+        let range = KeyRange::new(Some(b"c".to_vec()), Some(b"d".to_vec()), false);
+        assert_eq!(range_share(&files, &range), 0.25);
+        assert_eq!(range_share(&files, &KeyRange::prefix(b"e".to_vec())), 0.75);
+        assert_eq!(range_share(&files, &KeyRange::prefix(b"x".to_vec())), 0.0);
+        assert_eq!(range_share(&[], &KeyRange::prefix(b"x".to_vec())), 1.0);
+    }
+}
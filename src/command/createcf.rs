@@ -0,0 +1,102 @@
+use crate::command::traits::Command;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use rocksdb::{DBCompressionType, Options, SliceTransform, DB};
+use simple_error::SimpleError;
+use std::boxed::Box;
+use std::error::Error;
+
+const COMPRESSION_TYPES: &[&str] = &["none", "snappy", "zlib", "bz2", "lz4", "lz4hc", "zstd"];
+
+fn parse_compression_type(name: &str) -> Result<DBCompressionType, SimpleError> {
+    match name {
+        "none" => Ok(DBCompressionType::None),
+        "snappy" => Ok(DBCompressionType::Snappy),
+        "zlib" => Ok(DBCompressionType::Zlib),
+        "bz2" => Ok(DBCompressionType::Bz2),
+        "lz4" => Ok(DBCompressionType::Lz4),
+        "lz4hc" => Ok(DBCompressionType::Lz4hc),
+        "zstd" => Ok(DBCompressionType::Zstd),
+        _ => Err(SimpleError::new(format!(
+            "Unknown compression type: {}",
+            name
+        ))),
+    }
+}
+
+#[derive(Debug)]
+pub struct CreateCf<'a> {
+    db: DB,
+    name: &'a str,
+    compression: Option<DBCompressionType>,
+    prefix_length: Option<usize>,
+}
+
+impl<'a> CreateCf<'a> {
+    pub fn create(matches: &'a ArgMatches<'a>) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
+        let mut opts = Self::build_options(matches);
+        let db = Self::open_db(matches, &mut opts)?;
+        let subcommand_matches = matches.subcommand_matches(Self::name()).unwrap();
+        Ok(std::boxed::Box::new(CreateCf {
+            db,
+            name: subcommand_matches.value_of("NAME").unwrap(),
+            compression: match subcommand_matches.value_of("compression") {
+                None => None,
+                Some(compression) => Some(parse_compression_type(compression)?),
+            },
+            prefix_length: match subcommand_matches.value_of("prefix_length") {
+                None => None,
+                Some(length) => Some(length.parse::<usize>()?),
+            },
+        }))
+    }
+}
+
+impl<'a> Command for CreateCf<'a> {
+    fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut cf_opts = Options::default();
+        if let Some(compression) = self.compression {
+            cf_opts.set_compression_type(compression);
+        }
+        if let Some(length) = self.prefix_length {
+            cf_opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(length));
+        }
+        self.db.create_cf(self.name, &cf_opts)?;
+        println!("OK");
+        Ok(())
+    }
+
+    fn args() -> App<'static, 'static> {
+        SubCommand::with_name(Self::name())
+            .about("Creates column family")
+            .arg(
+                Arg::with_name("compression")
+                    .long("compression")
+                    .help("Compression of the column family")
+                    .required(false)
+                    .takes_value(true)
+                    .possible_values(COMPRESSION_TYPES),
+            )
+            .arg(
+                Arg::with_name("prefix_length")
+                    .long("prefix-length")
+                    .value_name("LENGTH")
+                    .help("Sets fixed length prefix extractor for the column family")
+                    .required(false)
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("NAME")
+                    .help("Name of the column family to create")
+                    .required(true)
+                    .index(1),
+            )
+    }
+
+    fn modifies_db() -> bool {
+        true
+    }
+
+    fn name() -> &'static str {
+        "createcf"
+    }
+}
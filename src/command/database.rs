@@ -0,0 +1,148 @@
+use clap::{Arg, ArgMatches};
+use rocksdb::{ColumnFamily, ColumnFamilyDescriptor, Options, DB};
+use simple_error::SimpleError;
+use std::boxed::Box;
+use std::error::Error;
+
+/// Defines how the database is opened
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OpenMode<'a> {
+    ReadWrite,
+    /// Does not take the DB lock, so a DB owned by a running process can be read
+    ReadOnly,
+    /// Opens the DB as a secondary instance keeping its own files in the given directory
+    Secondary(&'a str),
+}
+
+impl<'a> OpenMode<'a> {
+    pub fn from_matches(matches: &'a ArgMatches<'a>) -> Self {
+        if let Some(secondary_path) = matches.value_of("secondary") {
+            OpenMode::Secondary(secondary_path)
+        } else if matches.is_present("read_only") {
+            OpenMode::ReadOnly
+        } else {
+            OpenMode::ReadWrite
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            OpenMode::ReadWrite => "read-write",
+            OpenMode::ReadOnly => "read-only",
+            OpenMode::Secondary(_) => "secondary",
+        }
+    }
+}
+
+/// Opens the database given in the command line according to the requested open mode.
+///
+/// Commands which modify the DB are refused unless the DB is opened for read-write.
+pub fn open<'a>(
+    matches: &'a ArgMatches<'a>,
+    opts: &mut Options,
+    command: &str,
+    modifies_db: bool,
+) -> Result<DB, Box<dyn Error>> {
+    let path = matches.value_of("db").unwrap();
+    // RocksDB refuses to open a DB unless all of its column families are listed
+    let mut cfs = list_column_families(opts, path);
+    for name in column_families(matches) {
+        if !cfs.iter().any(|cf| cf == name) {
+            cfs.push(name.to_string());
+        }
+    }
+    let mode = OpenMode::from_matches(matches);
+    check_access(mode, command, modifies_db)?;
+    let db = match mode {
+        // Column families opened by name get default options, so they would miss the prefix
+        // extractor. Read-only and secondary instances can only open them by name.
+        OpenMode::ReadWrite => DB::open_cf_descriptors(
+            opts,
+            path,
+            cfs.iter()
+                .map(|name| ColumnFamilyDescriptor::new(name, opts.clone())),
+        )?,
+        OpenMode::ReadOnly => DB::open_cf_for_read_only(opts, path, &cfs, false)?,
+        OpenMode::Secondary(secondary_path) => {
+            // Secondary instances have to keep all the files open
+            opts.set_max_open_files(-1);
+            let db = DB::open_cf_as_secondary(opts, path, secondary_path, &cfs)?;
+            db.try_catch_up_with_primary()?;
+            db
+        }
+    };
+    Ok(db)
+}
+
+/// Refuses commands which modify the DB unless it is opened for read-write
+pub fn check_access(
+    mode: OpenMode,
+    command: &str,
+    modifies_db: bool,
+) -> Result<(), Box<dyn Error>> {
+    if modifies_db && mode != OpenMode::ReadWrite {
+        return Err(Box::new(SimpleError::new(format!(
+            "{} is not allowed in {} mode",
+            command,
+            mode.name()
+        ))));
+    }
+    Ok(())
+}
+
+/// Lists column families of an existing DB, a DB which is yet to be created has none
+pub fn list_column_families(opts: &Options, path: &str) -> Vec<String> {
+    DB::list_cf(opts, path).unwrap_or_default()
+}
+
+/// Column families given with `--column_family`
+pub fn column_families<'a>(matches: &'a ArgMatches<'a>) -> Vec<&'a str> {
+    matches.values_of("column_family").unwrap().collect()
+}
+
+pub fn all_column_families_arg() -> Arg<'static, 'static> {
+    Arg::with_name("all_column_families")
+        .long("all-column-families")
+        .help("Operate on all column families of the DB")
+        .required(false)
+        .takes_value(false)
+}
+
+/// Column families for commands supporting `--all-column-families`
+pub fn target_column_families<'a>(
+    matches: &'a ArgMatches<'a>,
+    subcommand_matches: &'a ArgMatches<'a>,
+) -> Vec<String> {
+    if subcommand_matches.is_present("all_column_families") {
+        list_column_families(&Options::default(), matches.value_of("db").unwrap())
+    } else {
+        column_families(matches)
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+}
+
+/// The column family for commands operating on a single one
+pub fn column_family<'a>(
+    matches: &'a ArgMatches<'a>,
+    command: &str,
+) -> Result<&'a str, Box<dyn Error>> {
+    match column_families(matches).as_slice() {
+        [name] => Ok(name),
+        _ => Err(Box::new(SimpleError::new(format!(
+            "{} operates on a single column family",
+            command
+        )))),
+    }
+}
+
+pub fn cf_handle<'d>(db: &'d DB, name: &str) -> Result<&'d ColumnFamily, Box<dyn Error>> {
+    match db.cf_handle(name) {
+        Some(cf) => Ok(cf),
+        None => Err(Box::new(SimpleError::new(format!(
+            "Column family not found: {}",
+            name
+        )))),
+    }
+}
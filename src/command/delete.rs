@@ -1,43 +1,48 @@
+use crate::command::database;
 use crate::command::traits::Command;
-use crate::utils;
+use crate::utils::codec::{self, Codec};
 use clap::{App, Arg, ArgMatches, SubCommand};
 use rocksdb::DB;
 use std::boxed::Box;
 use std::error::Error;
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct Delete<'a> {
-    db: DB,
+    db: Rc<DB>,
+    column_family: &'a str,
     key: &'a str,
-    key_hex: bool,
+    key_codec: Codec,
 }
 
 impl<'a> Delete<'a> {
     pub fn create(matches: &'a ArgMatches<'a>) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
-        let opts = Self::build_options(matches);
-        let db = DB::open_cf(
-            &opts,
-            matches.value_of("db").unwrap(),
-            &[matches.value_of("column_family").unwrap()],
-        )?;
+        let mut opts = Self::build_options(matches);
+        let db = Self::open_db(matches, &mut opts)?;
         let subcommand_matches = matches.subcommand_matches(Self::name()).unwrap();
+        Self::with_db(Rc::new(db), matches, subcommand_matches)
+    }
+
+    /// Creates the command operating on an already opened DB
+    pub fn with_db(
+        db: Rc<DB>,
+        matches: &'a ArgMatches<'a>,
+        subcommand_matches: &'a ArgMatches<'a>,
+    ) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
         Ok(std::boxed::Box::new(Delete {
             db,
+            column_family: database::column_family(matches, Self::name())?,
             key: subcommand_matches.value_of("KEY").unwrap(),
-            key_hex: subcommand_matches.is_present("key_hex")
-                || subcommand_matches.is_present("hex"),
+            key_codec: codec::key_codec(subcommand_matches)?.unwrap_or_default(),
         }))
     }
 }
 
 impl<'a> Command for Delete<'a> {
     fn run(&mut self) -> Result<(), Box<dyn Error>> {
-        let k = if self.key_hex {
-            utils::hex::decode(&self.key)?
-        } else {
-            self.key.as_bytes().into()
-        };
-        self.db.delete(k)?;
+        let cf = database::cf_handle(&self.db, self.column_family)?;
+        let k = self.key_codec.decode(self.key)?;
+        self.db.delete_cf(cf, k)?;
         println!("OK");
         Ok(())
     }
@@ -52,13 +57,7 @@ impl<'a> Command for Delete<'a> {
                     .required(false)
                     .takes_value(false),
             )
-            .arg(
-                Arg::with_name("key_hex")
-                    .long("key_hex")
-                    .help("Key provided in hex format")
-                    .required(false)
-                    .takes_value(false),
-            )
+            .args(&codec::key_args())
             .arg(
                 Arg::with_name("KEY")
                     .help("Key to delete")
@@ -67,6 +66,10 @@ impl<'a> Command for Delete<'a> {
             )
     }
 
+    fn modifies_db() -> bool {
+        true
+    }
+
     fn name() -> &'static str {
         "delete"
     }
@@ -1,62 +1,82 @@
+use crate::command::database;
+use crate::command::range::{self, KeyRange};
 use crate::command::traits::Command;
-use crate::utils;
+use crate::utils::codec::{self, Codec};
 use clap::{App, Arg, ArgMatches, SubCommand};
-use rocksdb::{DB, DEFAULT_COLUMN_FAMILY_NAME};
+use rocksdb::{IteratorMode, DB};
 use std::boxed::Box;
 use std::error::Error;
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct DeleteRange<'a> {
-    db: DB,
-    from_key: &'a str,
-    to_key: &'a str,
-    key_hex: bool,
+    db: Rc<DB>,
+    column_family: &'a str,
+    from_key: Option<&'a str>,
+    to_key: Option<&'a str>,
+    prefix: Option<&'a str>,
+    inclusive: bool,
+    key_codec: Codec,
 }
 
 impl<'a> DeleteRange<'a> {
     pub fn create(matches: &'a ArgMatches<'a>) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
-        let opts = Self::build_options(matches);
-        let db = DB::open_cf(
-            &opts,
-            matches.value_of("db").unwrap(),
-            &[matches.value_of("column_family").unwrap()],
-        )?;
+        let mut opts = Self::build_options(matches);
+        let db = Self::open_db(matches, &mut opts)?;
         let subcommand_matches = matches.subcommand_matches(Self::name()).unwrap();
+        Self::with_db(Rc::new(db), matches, subcommand_matches)
+    }
+
+    /// Creates the command operating on an already opened DB
+    pub fn with_db(
+        db: Rc<DB>,
+        matches: &'a ArgMatches<'a>,
+        subcommand_matches: &'a ArgMatches<'a>,
+    ) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
         Ok(std::boxed::Box::new(DeleteRange {
             db,
-            from_key: subcommand_matches.value_of("BEGIN KEY").unwrap(),
-            to_key: subcommand_matches.value_of("END KEY").unwrap(),
-            key_hex: subcommand_matches.is_present("key_hex")
-                || subcommand_matches.is_present("hex"),
+            column_family: database::column_family(matches, Self::name())?,
+            from_key: subcommand_matches.value_of("BEGIN KEY"),
+            to_key: subcommand_matches.value_of("END KEY"),
+            prefix: subcommand_matches.value_of("prefix"),
+            inclusive: subcommand_matches.is_present("inclusive"),
+            key_codec: codec::key_codec(subcommand_matches)?.unwrap_or_default(),
         }))
     }
 }
 
 impl<'a> Command for DeleteRange<'a> {
     fn run(&mut self) -> Result<(), Box<dyn Error>> {
-        let (from, to) = if self.key_hex {
-            (
-                utils::hex::decode(&self.from_key)?,
-                utils::hex::decode(&self.to_key)?,
-            )
-        } else {
-            (
-                self.from_key.as_bytes().into(),
-                self.to_key.as_bytes().into(),
-            )
+        let cf = database::cf_handle(&self.db, self.column_family)?;
+        let range = match self.prefix {
+            Some(prefix) => KeyRange::prefix(self.key_codec.decode(prefix)?),
+            None => KeyRange::new(
+                Some(self.key_codec.decode(self.from_key.unwrap())?),
+                Some(self.key_codec.decode(self.to_key.unwrap())?),
+                self.inclusive,
+            ),
         };
-        self.db.delete_range_cf(
-            self.db.cf_handle(DEFAULT_COLUMN_FAMILY_NAME).unwrap(),
-            from,
-            to,
-        )?;
+        let start = range.start.unwrap_or_default();
+        match range.end {
+            Some(end) => self.db.delete_range_cf(cf, start, end)?,
+            None => {
+                // Only prefixes have no end, every key from such a prefix on starts with it
+                let last = self.db.iterator_cf(cf, IteratorMode::End).next();
+                if let Some((last, _)) = last {
+                    if *last >= *start {
+                        self.db.delete_range_cf(cf, &start[..], &last[..])?;
+                        self.db.delete_cf(cf, &last)?;
+                    }
+                }
+            }
+        }
         println!("OK");
         Ok(())
     }
 
     fn args() -> App<'static, 'static> {
         SubCommand::with_name(Self::name())
-            .about("Delete range from <BEGIN KEY> to <END KEY>")
+            .about("Delete range from <BEGIN KEY> to <END KEY>, or all the keys with --prefix")
             .arg(
                 Arg::with_name("hex")
                     .long("hex")
@@ -64,27 +84,27 @@ impl<'a> Command for DeleteRange<'a> {
                     .required(false)
                     .takes_value(false),
             )
-            .arg(
-                Arg::with_name("key_hex")
-                    .long("key_hex")
-                    .help("Key provided in hex format")
-                    .required(false)
-                    .takes_value(false),
-            )
+            .args(&codec::key_args())
+            .arg(range::inclusive_arg().conflicts_with("prefix"))
+            .arg(range::prefix_arg().conflicts_with_all(&["BEGIN KEY", "END KEY"]))
             .arg(
                 Arg::with_name("BEGIN KEY")
                     .help("Delete from this key")
-                    .required(true)
+                    .required_unless("prefix")
                     .index(1),
             )
             .arg(
                 Arg::with_name("END KEY")
                     .help("Delete until this key")
-                    .required(true)
+                    .required_unless("prefix")
                     .index(2),
             )
     }
 
+    fn modifies_db() -> bool {
+        true
+    }
+
     fn name() -> &'static str {
         "deleterange"
     }
@@ -0,0 +1,81 @@
+use crate::command::traits::Command;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use rocksdb::DB;
+use simple_error::SimpleError;
+use std::boxed::Box;
+use std::error::Error;
+use std::io::Write;
+
+#[derive(Debug)]
+pub struct DropCf<'a> {
+    db: DB,
+    name: &'a str,
+    confirmed: bool,
+}
+
+impl<'a> DropCf<'a> {
+    pub fn create(matches: &'a ArgMatches<'a>) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
+        let mut opts = Self::build_options(matches);
+        let db = Self::open_db(matches, &mut opts)?;
+        let subcommand_matches = matches.subcommand_matches(Self::name()).unwrap();
+        Ok(std::boxed::Box::new(DropCf {
+            db,
+            name: subcommand_matches.value_of("NAME").unwrap(),
+            confirmed: subcommand_matches.is_present("yes"),
+        }))
+    }
+
+    fn confirm(&self) -> Result<bool, Box<dyn Error>> {
+        print!(
+            "Column family {} and all its data will be dropped, continue? [y/N] ",
+            self.name
+        );
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+    }
+}
+
+impl<'a> Command for DropCf<'a> {
+    fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.name == rocksdb::DEFAULT_COLUMN_FAMILY_NAME {
+            return Err(Box::new(SimpleError::new(
+                "Default column family cannot be dropped",
+            )));
+        }
+        if !self.confirmed && !self.confirm()? {
+            println!("Aborted");
+            return Ok(());
+        }
+        self.db.drop_cf(self.name)?;
+        println!("OK");
+        Ok(())
+    }
+
+    fn args() -> App<'static, 'static> {
+        SubCommand::with_name(Self::name())
+            .about("Drops column family with all its data")
+            .arg(
+                Arg::with_name("yes")
+                    .long("yes")
+                    .help("Drops without asking for confirmation")
+                    .required(false)
+                    .takes_value(false),
+            )
+            .arg(
+                Arg::with_name("NAME")
+                    .help("Name of the column family to drop")
+                    .required(true)
+                    .index(1),
+            )
+    }
+
+    fn modifies_db() -> bool {
+        true
+    }
+
+    fn name() -> &'static str {
+        "dropcf"
+    }
+}
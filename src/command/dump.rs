@@ -1,126 +1,413 @@
+use crate::command::database;
+use crate::command::filter::{self, Counts, Filter};
+use crate::command::query::{self, Query};
+use crate::command::range::{self, KeyRange};
 use crate::command::traits::Command;
+use crate::dumpfile::{self, DumpFormat};
+use crate::printer::{self, Encoding, OutputFormat, Printer};
 use crate::utils;
+use crate::utils::codec::{self, Codec};
+use crate::utils::compression::Compression;
 use clap::{App, Arg, ArgMatches, SubCommand};
-use rocksdb::{Direction, IteratorMode, DB};
+use rocksdb::{LiveFile, Snapshot, DB};
+use serde_json::json;
+use simple_error::SimpleError;
 use std::boxed::Box;
-use std::cmp;
 use std::error::Error;
-use std::io::Write;
+use std::io::{self, BufWriter, Write};
+use std::sync::Mutex;
 
-const DELIM: &str = " ==> ";
+pub const DELIM: &str = " ==> ";
+const MANIFEST: &str = "manifest.json";
 
-fn compare(first: &[u8], second: &[u8]) -> i32 {
-    for i in 0..cmp::min(first.len(), second.len()) {
-        //
-        if first[i] < second[i] {
-            return -1;
-        }
-        if first[i] > second[i] {
-            return 1;
+/// Picks up to `parts - 1` keys splitting `[start, end)` into ranges of similar size.
+///
+/// Live file metadata does not tell the column family of a file, so the boundaries of all the
+/// files are used for every column family.
+fn split_points(
+    files: &[LiveFile],
+    start: Option<&[u8]>,
+    end: Option<&[u8]>,
+    parts: usize,
+) -> Vec<Vec<u8>> {
+    let mut boundaries: Vec<(&[u8], usize)> = files
+        .iter()
+        .filter_map(|file| file.start_key.as_deref().map(|key| (key, file.size)))
+        .filter(|(key, _)| start.is_none_or(|s| *key > s) && end.is_none_or(|e| *key < e))
+        .collect();
+    boundaries.sort_unstable();
+    let total: usize = boundaries.iter().map(|(_, size)| size).sum();
+    let mut points: Vec<Vec<u8>> = Vec::new();
+    // Size of the files starting before the current one
+    let mut preceding = 0;
+    for (key, size) in boundaries {
+        let next = points.len() + 1;
+        if next < parts
+            && preceding * parts >= total * next
+            && points.last().is_none_or(|last| last.as_slice() < key)
+        {
+            points.push(key.to_vec());
         }
+        preceding += size;
     }
-    0
+    points
+}
+
+/// Keys of a column family
+#[derive(Debug)]
+struct Range {
+    column_family: String,
+    keys: KeyRange,
+}
+
+/// A range dumped into its own file by a parallel dump
+#[derive(Debug)]
+struct Shard {
+    file: String,
+    range: Range,
 }
 
 #[derive(Debug)]
 pub struct Dump<'a> {
     db: DB,
-    from: Option<&'a str>,
-    to: Option<&'a str>,
-    key_hex: bool,
-    value_hex: bool,
+    column_families: Vec<String>,
+    range: KeyRange,
+    prefix_length: Option<usize>,
+    filter: Filter,
+    query: Query,
+    encoding: Encoding,
     max_keys: Option<usize>,
-    output_file_path: &'a std::path::Path,
+    /// Output file, `-` for stdout, or directory of a parallel dump
+    output: &'a str,
+    compression: Compression,
+    output_format: OutputFormat,
+    format: DumpFormat,
+    db_path: &'a str,
+    parallel: Option<usize>,
 }
 
 impl<'a> Dump<'a> {
     pub fn create(matches: &'a ArgMatches<'a>) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
-        let opts = Self::build_options(matches);
-        let db = DB::open_cf(
-            &opts,
-            matches.value_of("db").unwrap(),
-            &[matches.value_of("column_family").unwrap()],
-        )?;
+        let mut opts = Self::build_options(matches);
         let subcommand_matches = matches.subcommand_matches(Self::name()).unwrap();
+        range::set_prefix_extractor(&mut opts, subcommand_matches)?;
+        let db = Self::open_db(matches, &mut opts)?;
+        let column_families = database::target_column_families(matches, subcommand_matches);
+        let output = subcommand_matches.value_of("output").unwrap();
+        let compression = match subcommand_matches.value_of("compression") {
+            Some(compression) => compression.parse()?,
+            None => Compression::from_path(output),
+        };
+        let parallel = match subcommand_matches.value_of("parallel") {
+            None => None,
+            Some(parallel) => Some(parallel.parse::<usize>()?),
+        };
+        if parallel == Some(0) {
+            return Err(Box::new(SimpleError::new(
+                "Number of parallel dumps must be positive",
+            )));
+        }
+        if parallel.is_some() && output == "-" {
+            return Err(Box::new(SimpleError::new(
+                "Parallel dump requires an output directory",
+            )));
+        }
+        let format: DumpFormat = subcommand_matches.value_of("format").unwrap().parse()?;
+        let query = Query::from_matches(subcommand_matches)?;
+        if query.is_projection() && format == DumpFormat::Binary {
+            return Err(Box::new(SimpleError::new(
+                "Projections can not be loaded back, so --select requires a text dump",
+            )));
+        }
+        let encoding = Encoding::from_matches(matches, subcommand_matches)?;
         Ok(std::boxed::Box::new(Dump {
             db,
-            from: subcommand_matches.value_of("from"),
-            to: subcommand_matches.value_of("to"),
-            key_hex: subcommand_matches.is_present("key_hex")
-                || subcommand_matches.is_present("hex"),
-            value_hex: subcommand_matches.is_present("value_hex")
-                || subcommand_matches.is_present("hex"),
+            column_families,
+            range: KeyRange::from_matches(subcommand_matches, |key| {
+                encoding.decode_key(None, key)
+            })?,
+            prefix_length: range::prefix_length(subcommand_matches)?,
+            filter: Filter::from_matches(subcommand_matches)?,
+            query,
+            encoding,
             max_keys: match subcommand_matches.value_of("max_keys") {
                 None => None,
                 Some(max) => Some(max.parse::<usize>().unwrap()),
             },
-            output_file_path: std::path::Path::new(subcommand_matches.value_of("output").unwrap()),
+            output,
+            compression,
+            output_format: matches.value_of("output_format").unwrap().parse()?,
+            format,
+            db_path: matches.value_of("db").unwrap(),
+            parallel,
         }))
     }
 
-    fn print_key_value(&self, key: &[u8], value: &[u8]) -> String {
-        let k = if self.key_hex {
-            utils::hex::encode(key)
-        } else {
-            String::from_utf8_lossy(key).into()
+    /// Refuses records a text dump can not load back, UTF-8 text replaces the invalid bytes
+    fn check_text(
+        &self,
+        column_family: &str,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<(), Box<dyn Error>> {
+        let lossy = |codec: &Codec, data: &[u8]| {
+            matches!(codec, Codec::Utf8) && std::str::from_utf8(data).is_err()
         };
-        let v = if self.value_hex {
-            utils::hex::encode(value)
+        let field = if lossy(self.encoding.key_codec(Some(column_family), key), key) {
+            "Key"
+        } else if lossy(self.encoding.value_codec(Some(column_family), key), value) {
+            "Value"
         } else {
-            String::from_utf8_lossy(value).into()
+            return Ok(());
         };
-        format!("{}{}{}", k, DELIM, v)
+        Err(Box::new(SimpleError::new(format!(
+            "{} of key {} is not valid UTF-8, dump it with --hex or --format binary",
+            field,
+            utils::hex::encode(key)
+        ))))
     }
-}
 
-impl<'a> Command for Dump<'a> {
-    fn run(&mut self) -> Result<(), Box<dyn Error>> {
-        let iter = match self.from {
-            None => self.db.iterator(IteratorMode::Start),
-            Some(from) => {
-                let f = if self.key_hex {
-                    utils::hex::decode(from)?
-                } else {
-                    Vec::from(from.as_bytes())
-                };
-                self.db
-                    .iterator(IteratorMode::From(f.as_ref(), Direction::Forward))
-            }
+    /// Dumps the ranges into the output file, `-` for stdout, returns the numbers of examined
+    /// and dumped keys
+    fn dump_to(
+        &self,
+        snapshot: &Snapshot,
+        sequence_number: u64,
+        output: &str,
+        ranges: &[Range],
+    ) -> Result<Counts, Box<dyn Error>> {
+        let file = if output == "-" {
+            None
+        } else {
+            Some(std::fs::File::create(output)?)
         };
-        let end = match self.to {
-            None => None,
-            Some(to) => {
-                if self.key_hex {
-                    Some(utils::hex::decode(to)?)
+        let out: Box<dyn Write> = match file {
+            Some(ref f) => Box::new(BufWriter::new(f)),
+            None => Box::new(BufWriter::new(io::stdout())),
+        };
+        let mut encoder = self.compression.encoder(out)?;
+        let mut printer: Box<dyn Printer> = match self.format {
+            DumpFormat::Text if self.query.is_projection() => printer::create(
+                self.output_format,
+                Box::new(&mut encoder),
+                // Projections are printed as JSON text in place of the values
+                Encoding {
+                    value: Some(Codec::Utf8),
+                    ..self.encoding.clone()
+                },
+                Some(DELIM),
+            ),
+            DumpFormat::Text => printer::create(
+                self.output_format,
+                Box::new(&mut encoder),
+                self.encoding.clone(),
+                Some(DELIM),
+            ),
+            DumpFormat::Binary => Box::new(dumpfile::Writer::new(
+                &mut encoder,
+                &dumpfile::Header {
+                    source: self.db_path.to_string(),
+                    sequence_number,
+                    column_families: self.column_families.clone(),
+                },
+            )?),
+        };
+        // Binary dumps always record the column family
+        let show_column_family =
+            self.format == DumpFormat::Binary || self.column_families.len() > 1;
+        let mut counts = Counts::default();
+        'ranges: for range in ranges {
+            let cf = database::cf_handle(&self.db, &range.column_family)?;
+            printer.column_family(&range.column_family)?;
+            let iter = snapshot.iterator_cf_opt(
+                cf,
+                range.keys.read_options(self.prefix_length, false)?,
+                range.keys.mode(),
+            );
+            for (key, value) in iter {
+                if let Some(max) = self.max_keys {
+                    if counts.matched >= max {
+                        break 'ranges;
+                    }
+                }
+                if !range.keys.contains(&key) {
+                    break;
+                }
+                counts.examined += 1;
+                if !self.filter.matches(&key, &value)
+                    || !self
+                        .query
+                        .matches(&self.encoding, &range.column_family, &key, &value)
+                {
+                    continue;
+                }
+                let column_family = if show_column_family {
+                    Some(range.column_family.as_str())
                 } else {
-                    Some(Vec::from(to.as_bytes()))
+                    None
+                };
+                let projection =
+                    self.query
+                        .select(&self.encoding, &range.column_family, &key, &value);
+                let value = projection.as_deref().unwrap_or(&value);
+                if self.format == DumpFormat::Text {
+                    self.check_text(&range.column_family, &key, value)?;
                 }
+                printer.print(column_family, key.as_ref(), value)?;
+                counts.matched += 1;
             }
-        };
+        }
+        printer.finish()?;
+        drop(printer);
+        encoder.finish()?;
+        if let Some(f) = file {
+            f.sync_all()?;
+        }
+        Ok(counts)
+    }
 
-        let mut f = std::fs::File::create(self.output_file_path)?;
-        for (i, (key, value)) in iter.enumerate() {
-            if let Some(max) = self.max_keys {
-                if i >= max {
-                    break;
+    /// Splits the column families into ranges by SST file boundaries and dumps them
+    /// concurrently into shard files of the output directory described by its manifest
+    fn dump_parallel(
+        &self,
+        snapshot: &Snapshot,
+        sequence_number: u64,
+        parallel: usize,
+    ) -> Result<Counts, Box<dyn Error>> {
+        let directory = std::path::Path::new(self.output);
+        std::fs::create_dir_all(directory)?;
+        let points = split_points(
+            &self.db.live_files()?,
+            self.range.start.as_deref(),
+            self.range.end.as_deref(),
+            parallel,
+        );
+        let mut bounds = vec![self.range.start.clone()];
+        bounds.extend(points.into_iter().map(Some));
+        bounds.push(self.range.end.clone());
+        let mut shards = Vec::new();
+        for name in &self.column_families {
+            for range in bounds.windows(2) {
+                let mut file = format!("shard-{:04}.{}", shards.len(), self.format.extension());
+                if let Some(extension) = self.compression.extension() {
+                    file = format!("{}.{}", file, extension);
                 }
+                shards.push(Shard {
+                    file,
+                    range: Range {
+                        column_family: name.clone(),
+                        keys: self.range.slice(range[0].clone(), range[1].clone()),
+                    },
+                });
             }
-            match end {
-                None => {}
-                Some(ref e) => {
-                    if compare(key.as_ref(), e.as_ref()) >= 0 {
-                        break;
-                    }
-                }
+        }
+
+        let count = shards.len();
+        let jobs = Mutex::new(shards.iter().enumerate());
+        // Errors are not Send, so workers report them as strings
+        let results: Vec<Result<Vec<(usize, Counts)>, String>> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..parallel.min(count))
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut dumped = Vec::new();
+                        loop {
+                            let job = jobs.lock().unwrap().next();
+                            let (index, shard) = match job {
+                                Some(job) => job,
+                                None => return Ok(dumped),
+                            };
+                            let path = directory.join(&shard.file);
+                            let counts = self
+                                .dump_to(
+                                    snapshot,
+                                    sequence_number,
+                                    &path.to_string_lossy(),
+                                    std::slice::from_ref(&shard.range),
+                                )
+                                .map_err(|error| format!("{}: {}", shard.file, error))?;
+                            eprintln!(
+                                "Dumped {} ({}/{}): {} keys",
+                                shard.file,
+                                index + 1,
+                                count,
+                                counts.matched
+                            );
+                            dumped.push((index, counts));
+                        }
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect()
+        });
+        let mut keys = vec![0; count];
+        let mut total = Counts::default();
+        for result in results {
+            for (index, counts) in result.map_err(SimpleError::new)? {
+                keys[index] = counts.matched;
+                total += counts;
             }
-            writeln!(
-                &mut f,
-                "{}",
-                self.print_key_value(key.as_ref(), value.as_ref())
-            )?;
         }
-        f.flush()?;
+
+        let bound = |key: &Option<Vec<u8>>| key.as_ref().map(utils::hex::encode);
+        let codec_name = |codec: &Option<Codec>| match codec {
+            Some(codec) => codec.name(),
+            None if self.encoding.schema.is_some() => "schema",
+            None => Codec::Utf8.name(),
+        };
+        let manifest = json!({
+            "version": 1,
+            "source": self.db_path,
+            "sequence_number": sequence_number,
+            "format": self.format.name(),
+            "output_format": self.output_format.name(),
+            "compression": self.compression.name(),
+            "key_codec": codec_name(&self.encoding.key),
+            "value_codec": codec_name(&self.encoding.value),
+            "shards": shards
+                .iter()
+                .zip(keys)
+                .map(|(shard, keys)| json!({
+                    "file": shard.file,
+                    "column_family": shard.range.column_family,
+                    "from": bound(&shard.range.keys.start),
+                    "to": bound(&shard.range.keys.end),
+                    "keys": keys,
+                }))
+                .collect::<Vec<_>>(),
+        });
+        let mut f = std::fs::File::create(directory.join(MANIFEST))?;
+        serde_json::to_writer_pretty(&mut f, &manifest)?;
+        writeln!(f)?;
         f.sync_all()?;
+        Ok(total)
+    }
+}
+
+impl<'a> Command for Dump<'a> {
+    fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        // All the column families are dumped as of the same moment
+        let snapshot = self.db.snapshot();
+        let sequence_number = self.db.latest_sequence_number();
+        let counts = match self.parallel {
+            None => {
+                let ranges: Vec<Range> = self
+                    .column_families
+                    .iter()
+                    .map(|name| Range {
+                        column_family: name.clone(),
+                        keys: self.range.clone(),
+                    })
+                    .collect();
+                self.dump_to(&snapshot, sequence_number, self.output, &ranges)?
+            }
+            Some(parallel) => self.dump_parallel(&snapshot, sequence_number, parallel)?,
+        };
+        if self.filter.is_active() || self.query.is_active() {
+            eprintln!("{}", counts);
+        }
         Ok(())
     }
 
@@ -130,45 +417,29 @@ impl<'a> Command for Dump<'a> {
             .arg(
                 Arg::with_name("output")
                     .long("output")
-                    .help("File to where to save dump")
+                    .help("File to where to save dump, - for stdout, directory for parallel dumps")
                     .required(true)
                     .takes_value(true),
             )
+            .arg(codec::hex_arg())
+            .args(&codec::key_args())
+            .args(&codec::value_args())
+            .arg(dumpfile::format_arg())
             .arg(
-                Arg::with_name("hex")
-                    .long("hex")
-                    .help("Key and value provided in hex format")
-                    .required(false)
-                    .takes_value(false),
-            )
-            .arg(
-                Arg::with_name("value_hex")
-                    .long("value_hex")
-                    .help("Value provided in hex format")
-                    .required(false)
-                    .takes_value(false),
-            )
-            .arg(
-                Arg::with_name("key_hex")
-                    .long("key_hex")
-                    .help("Key provided in hex format")
+                Arg::with_name("compression")
+                    .long("compression")
+                    .help(
+                        "Compression of the dump, guessed by the output file extension by default",
+                    )
                     .required(false)
-                    .takes_value(false),
-            )
-            .arg(
-                Arg::with_name("from")
-                    .long("from")
-                    .help("key to dump from")
-                    .required(false)
-                    .takes_value(true),
-            )
-            .arg(
-                Arg::with_name("to")
-                    .long("to")
-                    .help("key to dump to")
-                    .required(false)
-                    .takes_value(true),
+                    .takes_value(true)
+                    .possible_values(Compression::VARIANTS),
             )
+            .arg(database::all_column_families_arg())
+            .args(&filter::args())
+            .args(&query::args())
+            .args(&range::args())
+            .arg(range::prefix_length_arg())
             .arg(
                 Arg::with_name("max_keys")
                     .long("max_keys")
@@ -176,9 +447,55 @@ impl<'a> Command for Dump<'a> {
                     .required(false)
                     .takes_value(true),
             )
+            .arg(
+                Arg::with_name("parallel")
+                    .long("parallel")
+                    .help("Number of ranges dumped concurrently into shard files of the output directory")
+                    .required(false)
+                    .takes_value(true)
+                    .conflicts_with("max_keys"),
+            )
     }
 
     fn name() -> &'static str {
         "dump"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn live_file(start_key: &[u8], size: usize) -> LiveFile {
+        LiveFile {
+            name: String::new(),
+            size,
+            level: 1,
+            start_key: Some(start_key.to_vec()),
+            end_key: None,
+            num_entries: 0,
+            num_deletions: 0,
+        }
+    }
+
+    #[test]
+    fn test_split_points() {
+        let files = [
+            live_file(b"d", 10),
+            live_file(b"a", 10),
+            live_file(b"g", 10),
+            live_file(b"j", 10),
+        ];
+        assert_eq!(split_points(&files, None, None, 1), Vec::<Vec<u8>>::new());
+        assert_eq!(split_points(&files, None, None, 2), vec![b"g".to_vec()]);
+        assert_eq!(
+            split_points(&files, None, None, 4),
+            vec![b"d".to_vec(), b"g".to_vec(), b"j".to_vec()]
+        );
+        assert_eq!(
+            split_points(&files, Some(b"b"), Some(b"j"), 8),
+            vec![b"g".to_vec()]
+        );
+        assert_eq!(split_points(&[], None, None, 4), Vec::<Vec<u8>>::new());
+    }
+}
@@ -0,0 +1,173 @@
+use crate::utils;
+use clap::{Arg, ArgMatches};
+use regex::bytes::Regex;
+use std::boxed::Box;
+use std::error::Error;
+use std::fmt;
+
+/// Predicates on raw keys and values, a record is kept if it satisfies all of them
+#[derive(Debug, Default)]
+pub struct Filter {
+    key_regex: Option<Regex>,
+    value_regex: Option<Regex>,
+    value_contains: Option<Vec<u8>>,
+    min_value_size: Option<usize>,
+    max_value_size: Option<usize>,
+}
+
+impl Filter {
+    pub fn from_matches(subcommand_matches: &ArgMatches) -> Result<Filter, Box<dyn Error>> {
+        let regex = |name| match subcommand_matches.value_of(name) {
+            Some(regex) => Regex::new(regex).map(Some),
+            None => Ok(None),
+        };
+        let size = |name| match subcommand_matches.value_of(name) {
+            Some(size) => size.parse::<usize>().map(Some),
+            None => Ok(None),
+        };
+        Ok(Filter {
+            key_regex: regex("key_regex")?,
+            value_regex: regex("value_regex")?,
+            value_contains: match subcommand_matches.value_of("value_contains") {
+                Some(bytes) if bytes.starts_with("0x") => Some(utils::hex::decode(bytes)?),
+                Some(bytes) => Some(bytes.as_bytes().to_vec()),
+                None => None,
+            },
+            min_value_size: size("min_value_size")?,
+            max_value_size: size("max_value_size")?,
+        })
+    }
+
+    /// Whether any predicate is given
+    pub fn is_active(&self) -> bool {
+        self.key_regex.is_some()
+            || self.value_regex.is_some()
+            || self.value_contains.is_some()
+            || self.min_value_size.is_some()
+            || self.max_value_size.is_some()
+    }
+
+    pub fn matches(&self, key: &[u8], value: &[u8]) -> bool {
+        self.key_regex
+            .as_ref()
+            .is_none_or(|regex| regex.is_match(key))
+            && self
+                .value_regex
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(value))
+            && self.value_contains.as_ref().is_none_or(|bytes| {
+                bytes.is_empty()
+                    || value
+                        .windows(bytes.len())
+                        .any(|window| window == &bytes[..])
+            })
+            && self.min_value_size.is_none_or(|size| value.len() >= size)
+            && self.max_value_size.is_none_or(|size| value.len() <= size)
+    }
+}
+
+/// Numbers of keys read and kept by a filtered iteration
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Counts {
+    pub examined: usize,
+    pub matched: usize,
+}
+
+impl std::ops::AddAssign for Counts {
+    fn add_assign(&mut self, other: Counts) {
+        self.examined += other.examined;
+        self.matched += other.matched;
+    }
+}
+
+impl fmt::Display for Counts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Examined {} keys, {} matched",
+            self.examined, self.matched
+        )
+    }
+}
+
+/// Filter arguments of commands iterating over keys
+pub fn args() -> [Arg<'static, 'static>; 5] {
+    [
+        Arg::with_name("key_regex")
+            .long("key-regex")
+            .value_name("REGEX")
+            .help("Only keys whose raw bytes match the regex")
+            .required(false)
+            .takes_value(true),
+        Arg::with_name("value_regex")
+            .long("value-regex")
+            .value_name("REGEX")
+            .help("Only values whose raw bytes match the regex")
+            .required(false)
+            .takes_value(true),
+        Arg::with_name("value_contains")
+            .long("value-contains")
+            .value_name("BYTES")
+            .help("Only values containing the bytes, given as text or as hex starting with 0x")
+            .required(false)
+            .takes_value(true),
+        Arg::with_name("min_value_size")
+            .long("min-value-size")
+            .value_name("BYTES")
+            .help("Only values of at least the size")
+            .required(false)
+            .takes_value(true),
+        Arg::with_name("max_value_size")
+            .long("max-value-size")
+            .value_name("BYTES")
+            .help("Only values of at most the size")
+            .required(false)
+            .takes_value(true),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let filter = Filter::default();
+        assert!(!filter.is_active());
+        assert!(filter.matches(b"", b""));
+
+        let filter = Filter {
+            key_regex: Some(Regex::new("^user:[0-9]+$").unwrap()),
+            value_contains: Some(b"\x00\xff".to_vec()),
+            max_value_size: Some(4),
+            ..Default::default()
+        };
+        assert!(filter.is_active());
+        assert!(filter.matches(b"user:1", b"a\x00\xff"));
+        assert!(!filter.matches(b"user:x", b"a\x00\xff"));
+        assert!(!filter.matches(b"user:1", b"a\x00"));
+        assert!(!filter.matches(b"user:1", b"abc\x00\xff"));
+
+        let filter = Filter {
+            value_regex: Some(Regex::new("(?-u)\\xff").unwrap()),
+            min_value_size: Some(2),
+            ..Default::default()
+        };
+        assert!(filter.matches(b"k", b"\x00\xff"));
+        assert!(!filter.matches(b"k", b"\xff"));
+        assert!(!filter.matches(b"k", b"ab"));
+    }
+
+    #[test]
+    fn test_counts() {
+        let mut counts = Counts {
+            examined: 3,
+            matched: 1,
+        };
+        counts += Counts {
+            examined: 2,
+            matched: 2,
+        };
+        assert_eq!(counts.to_string(), "Examined 5 keys, 3 matched");
+    }
+}
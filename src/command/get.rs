@@ -1,56 +1,111 @@
+use crate::command::database;
+use crate::command::scan;
 use crate::command::traits::Command;
-use crate::utils;
+use crate::printer::{self, Encoding, OutputFormat, ValueEncoding};
+use crate::utils::codec::{self, Codec};
 use clap::{App, Arg, ArgMatches, SubCommand};
-use rocksdb::DB;
+use rocksdb::{Direction, IteratorMode, DB};
 use std::boxed::Box;
 use std::error::Error;
+use std::io::Write;
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct Get<'a> {
-    db: DB,
+    db: Rc<DB>,
+    column_family: &'a str,
     key: &'a str,
-    key_hex: bool,
-    value_hex: bool,
+    encoding: Encoding,
+    /// Overrides the value codec
+    value_encoding: Option<ValueEncoding>,
+    /// File the exact value bytes are written to
+    output: Option<&'a str>,
+    /// Looks up the greatest key less than or equal to the given one
+    seek_for_prev: bool,
+    output_format: OutputFormat,
 }
 
 impl<'a> Get<'a> {
     pub fn create(matches: &'a ArgMatches<'a>) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
-        let opts = Self::build_options(matches);
-        let db = DB::open_cf(
-            &opts,
-            matches.value_of("db").unwrap(),
-            &[matches.value_of("column_family").unwrap()],
-        )?;
+        let mut opts = Self::build_options(matches);
+        let db = Self::open_db(matches, &mut opts)?;
         let subcommand_matches = matches.subcommand_matches(Self::name()).unwrap();
+        Self::with_db(Rc::new(db), matches, subcommand_matches)
+    }
+
+    /// Creates the command operating on an already opened DB
+    pub fn with_db(
+        db: Rc<DB>,
+        matches: &'a ArgMatches<'a>,
+        subcommand_matches: &'a ArgMatches<'a>,
+    ) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
         Ok(std::boxed::Box::new(Get {
             db,
+            column_family: database::column_family(matches, Self::name())?,
             key: subcommand_matches.value_of("KEY").unwrap(),
-            key_hex: subcommand_matches.is_present("key_hex")
-                || subcommand_matches.is_present("hex"),
-            value_hex: subcommand_matches.is_present("value_hex")
-                || subcommand_matches.is_present("hex"),
+            encoding: Encoding::from_matches(matches, subcommand_matches)?,
+            value_encoding: match subcommand_matches.value_of("value_encoding") {
+                Some(encoding) => Some(encoding.parse()?),
+                None => None,
+            },
+            output: subcommand_matches.value_of("output"),
+            seek_for_prev: subcommand_matches.is_present("seek_for_prev"),
+            output_format: matches.value_of("output_format").unwrap().parse()?,
         }))
     }
 }
 
 impl<'a> Command for Get<'a> {
     fn run(&mut self) -> Result<(), Box<dyn Error>> {
-        let key = if self.key_hex {
-            utils::hex::decode(&self.key)?
+        let cf = database::cf_handle(&self.db, self.column_family)?;
+        let key = self
+            .encoding
+            .decode_key(Some(self.column_family), self.key)?;
+        let record = if self.seek_for_prev {
+            self.db
+                .iterator_cf(cf, IteratorMode::From(&key, Direction::Reverse))
+                .next()
+                .map(|(key, value)| (key.to_vec(), value.to_vec()))
         } else {
-            self.key.as_bytes().into()
+            self.db.get_cf(cf, &key)?.map(|value| (key, value))
         };
-        match self.db.get(key)? {
+        match record {
             None => {
                 eprintln!("Not Found");
             }
-            Some(value) => {
-                let output = if self.value_hex {
-                    utils::hex::encode(value)
+            Some((key, value)) => {
+                if let Some(output) = self.output {
+                    std::fs::write(output, &value)?;
+                    println!("OK");
+                } else if self.value_encoding == Some(ValueEncoding::Raw) {
+                    let mut stdout = std::io::stdout();
+                    stdout.write_all(&value)?;
+                    stdout.flush()?;
                 } else {
-                    String::from_utf8(value).unwrap()
-                };
-                println!("{}", output);
+                    // The printer gets the rendered value, so it is printed as is
+                    let codec = self.encoding.value_codec(Some(self.column_family), &key);
+                    let value = match self.value_encoding {
+                        Some(encoding) => encoding.render(&value)?,
+                        None if *codec != Codec::Utf8 => codec.encode(&value),
+                        None => ValueEncoding::Auto.render(&value)?,
+                    };
+                    let mut printer = printer::create(
+                        self.output_format,
+                        Box::new(std::io::stdout()),
+                        Encoding {
+                            value: Some(Codec::Utf8),
+                            ..self.encoding.clone()
+                        },
+                        // The found key is printed as it may differ from the given one
+                        if self.seek_for_prev {
+                            Some(scan::DELIM)
+                        } else {
+                            None
+                        },
+                    );
+                    printer.print(None, &key, value.as_bytes())?;
+                    printer.finish()?;
+                }
             }
         }
         Ok(())
@@ -59,24 +114,35 @@ impl<'a> Command for Get<'a> {
     fn args() -> App<'static, 'static> {
         SubCommand::with_name(Self::name())
             .about("Gets given key value to the DB")
+            .arg(codec::hex_arg())
+            .args(&codec::key_args())
+            .args(&codec::value_args())
             .arg(
-                Arg::with_name("hex")
-                    .long("hex")
-                    .help("Key and value provided in hex format")
+                Arg::with_name("value_encoding")
+                    .long("value-encoding")
+                    .help("How the value is printed, by default binary values are printed in hex")
                     .required(false)
-                    .takes_value(false),
+                    .takes_value(true)
+                    .possible_values(ValueEncoding::VARIANTS)
+                    .conflicts_with_all(&[
+                        "value_hex",
+                        "value_codec",
+                        "proto_descriptor",
+                        "bincode_schema",
+                    ]),
             )
             .arg(
-                Arg::with_name("value_hex")
-                    .long("value_hex")
-                    .help("Value provided in hex format")
+                Arg::with_name("output")
+                    .long("output")
+                    .help("Writes the exact value bytes to the file instead of printing it")
                     .required(false)
-                    .takes_value(false),
+                    .takes_value(true)
+                    .conflicts_with("value_encoding"),
             )
             .arg(
-                Arg::with_name("key_hex")
-                    .long("key_hex")
-                    .help("Key provided in hex format")
+                Arg::with_name("seek_for_prev")
+                    .long("seek-for-prev")
+                    .help("Gets the greatest key less than or equal to KEY and prints it along with the value")
                     .required(false)
                     .takes_value(false),
             )
@@ -0,0 +1,37 @@
+use crate::command::traits::Command;
+use clap::{App, ArgMatches, SubCommand};
+use rocksdb::DB;
+use std::boxed::Box;
+use std::error::Error;
+
+#[derive(Debug)]
+pub struct ListCf {
+    column_families: Vec<String>,
+}
+
+impl ListCf {
+    pub fn create<'a>(
+        matches: &'a ArgMatches<'a>,
+    ) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
+        let opts = Self::build_options(matches);
+        let column_families = DB::list_cf(&opts, matches.value_of("db").unwrap())?;
+        Ok(std::boxed::Box::new(ListCf { column_families }))
+    }
+}
+
+impl Command for ListCf {
+    fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        for name in &self.column_families {
+            println!("{}", name);
+        }
+        Ok(())
+    }
+
+    fn args() -> App<'static, 'static> {
+        SubCommand::with_name(Self::name()).about("Lists column families of the DB")
+    }
+
+    fn name() -> &'static str {
+        "listcf"
+    }
+}
@@ -0,0 +1,296 @@
+use crate::command::database;
+use crate::command::dump;
+use crate::command::traits::Command;
+use crate::dumpfile::{self, DumpFormat};
+use crate::printer::Encoding;
+use crate::utils::codec;
+use crate::utils::compression::{self, Compression};
+use clap::{App, Arg, ArgMatches, SubCommand};
+use rocksdb::{Options, WriteBatch, DB};
+use simple_error::SimpleError;
+use std::boxed::Box;
+use std::error::Error;
+use std::io::{self, BufRead, BufReader, Read};
+
+/// A record of the plain dump format: `[column family ==> ]key ==> value`
+#[derive(Debug, PartialEq)]
+struct Record<'l> {
+    column_family: Option<&'l str>,
+    key: &'l str,
+    value: &'l str,
+}
+
+/// Splits a dumped line, the value is the rest of the line so it may contain the delimiter
+fn parse_line(line: &str, with_column_family: bool) -> Option<Record<'_>> {
+    if with_column_family {
+        let mut fields = line.splitn(3, dump::DELIM);
+        Some(Record {
+            column_family: Some(fields.next()?),
+            key: fields.next()?,
+            value: fields.next()?,
+        })
+    } else {
+        let mut fields = line.splitn(2, dump::DELIM);
+        Some(Record {
+            column_family: None,
+            key: fields.next()?,
+            value: fields.next()?,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Load<'a> {
+    db: DB,
+    /// Column family for dumps without column family names
+    column_family: Option<&'a str>,
+    input: &'a str,
+    with_column_family: bool,
+    format: DumpFormat,
+    /// Detected by the magic number unless given
+    compression: Option<Compression>,
+    encoding: Encoding,
+    batch_size: usize,
+}
+
+impl<'a> Load<'a> {
+    pub fn create(matches: &'a ArgMatches<'a>) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
+        let mut opts = Self::build_options(matches);
+        let db = Self::open_db(matches, &mut opts)?;
+        let subcommand_matches = matches.subcommand_matches(Self::name()).unwrap();
+        let batch_size = subcommand_matches
+            .value_of("batch_size")
+            .unwrap()
+            .parse::<usize>()?;
+        if batch_size == 0 {
+            return Err(Box::new(SimpleError::new("Batch size must be positive")));
+        }
+        let with_column_family = subcommand_matches.is_present("with_column_family");
+        let format = subcommand_matches.value_of("format").unwrap().parse()?;
+        let column_family = if with_column_family || format == DumpFormat::Binary {
+            None
+        } else {
+            Some(database::column_family(matches, Self::name())?)
+        };
+        Ok(std::boxed::Box::new(Load {
+            db,
+            column_family,
+            input: subcommand_matches.value_of("input").unwrap(),
+            with_column_family,
+            format,
+            compression: match subcommand_matches.value_of("compression") {
+                Some(compression) => Some(compression.parse()?),
+                None => None,
+            },
+            encoding: Encoding::from_matches(matches, subcommand_matches)?,
+            batch_size,
+        }))
+    }
+
+    fn write(&self, batch: WriteBatch, loaded: usize) -> Result<(), Box<dyn Error>> {
+        self.db.write(batch)?;
+        eprintln!("Loaded {} keys", loaded);
+        Ok(())
+    }
+
+    /// Adds the record to the batch, writing the batch once it is full
+    fn put(
+        &self,
+        batch: &mut WriteBatch,
+        loaded: &mut usize,
+        column_family: &str,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<(), Box<dyn Error>> {
+        let cf = database::cf_handle(&self.db, column_family)?;
+        batch.put_cf(cf, key, value);
+        *loaded += 1;
+        if batch.len() >= self.batch_size {
+            self.write(std::mem::take(batch), *loaded)?;
+        }
+        Ok(())
+    }
+
+    fn load_text(
+        &self,
+        reader: Box<dyn BufRead>,
+        batch: &mut WriteBatch,
+        loaded: &mut usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut warned = false;
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if !warned && line.contains(char::REPLACEMENT_CHARACTER) {
+                eprintln!(
+                    "Warning: line {} contains U+FFFD, the dump may have replaced invalid UTF-8",
+                    number + 1
+                );
+                warned = true;
+            }
+            let record = match parse_line(&line, self.with_column_family) {
+                Some(record) => record,
+                None => {
+                    return Err(Box::new(SimpleError::new(format!(
+                        "Malformed line {}: expected '{}' delimited fields",
+                        number + 1,
+                        dump::DELIM.trim()
+                    ))))
+                }
+            };
+            let column_family = record.column_family.or(self.column_family).unwrap();
+            let key = self.encoding.decode_key(Some(column_family), record.key)?;
+            let value = self
+                .encoding
+                .decode_value(Some(column_family), &key, record.value)?;
+            self.put(batch, loaded, column_family, &key, &value)?;
+        }
+        Ok(())
+    }
+
+    /// Opens the decompressed input, stdin is read from the given copy of it if any
+    fn open<'r>(&self, stdin: Option<&'r [u8]>) -> Result<Box<dyn BufRead + 'r>, Box<dyn Error>> {
+        let input: Box<dyn Read + 'r> = match stdin {
+            Some(data) => Box::new(data),
+            None if self.input == "-" => Box::new(io::stdin()),
+            None => Box::new(std::fs::File::open(self.input)?),
+        };
+        let input = match self.compression {
+            Some(compression) => compression.decoder(input)?,
+            None => compression::auto_decoder(input)?,
+        };
+        Ok(Box::new(BufReader::new(input)))
+    }
+
+    /// Loads every column family section into the column family of the same name.
+    ///
+    /// The whole dump is read once to verify its checksum before anything is written, so a
+    /// truncated or corrupted dump leaves the DB untouched.
+    fn load_binary(
+        &mut self,
+        stdin: Option<&[u8]>,
+        batch: &mut WriteBatch,
+        loaded: &mut usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut reader = dumpfile::Reader::new(self.open(stdin)?)?;
+        while reader.next_entry()?.is_some() {}
+        let mut reader = dumpfile::Reader::new(self.open(stdin)?)?;
+        let header = reader.header();
+        eprintln!(
+            "Loading dump of {} at sequence number {} with column families {}",
+            header.source,
+            header.sequence_number,
+            header.column_families.join(", ")
+        );
+        let mut column_family = String::new();
+        while let Some(entry) = reader.next_entry()? {
+            match entry {
+                dumpfile::Entry::ColumnFamily(name) => {
+                    if self.db.cf_handle(&name).is_none() {
+                        self.db.create_cf(&name, &Options::default())?;
+                    }
+                    column_family = name;
+                }
+                dumpfile::Entry::Record(key, value) => {
+                    self.put(batch, loaded, &column_family, &key, &value)?
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Command for Load<'a> {
+    fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut batch = WriteBatch::default();
+        let mut loaded = 0;
+        match self.format {
+            DumpFormat::Text => self.load_text(self.open(None)?, &mut batch, &mut loaded)?,
+            DumpFormat::Binary if self.input == "-" => {
+                // Binary dumps are read twice, so stdin is kept in memory
+                let mut stdin = Vec::new();
+                io::stdin().read_to_end(&mut stdin)?;
+                self.load_binary(Some(&stdin), &mut batch, &mut loaded)?
+            }
+            DumpFormat::Binary => self.load_binary(None, &mut batch, &mut loaded)?,
+        }
+        if !batch.is_empty() {
+            self.write(batch, loaded)?;
+        }
+        println!("OK");
+        Ok(())
+    }
+
+    fn args() -> App<'static, 'static> {
+        SubCommand::with_name(Self::name())
+            .about("Loads a dump produced by the dump command in plain or binary format")
+            .arg(dumpfile::format_arg())
+            .arg(
+                Arg::with_name("compression")
+                    .long("compression")
+                    .help("Compression of the dump, detected by default")
+                    .required(false)
+                    .takes_value(true)
+                    .possible_values(Compression::VARIANTS),
+            )
+            .arg(
+                Arg::with_name("input")
+                    .long("input")
+                    .help("Dump file to load, - for stdin")
+                    .required(true)
+                    .takes_value(true),
+            )
+            .arg(codec::hex_arg())
+            .args(&codec::key_args())
+            .args(&codec::value_args())
+            .arg(
+                Arg::with_name("with_column_family")
+                    .long("with-column-family")
+                    .help("Text lines start with the column family, as dumped from several column families")
+                    .required(false)
+                    .takes_value(false),
+            )
+            .arg(
+                Arg::with_name("batch_size")
+                    .long("batch-size")
+                    .help("Number of keys written in a single batch")
+                    .required(false)
+                    .takes_value(true)
+                    .default_value("10000"),
+            )
+    }
+
+    fn modifies_db() -> bool {
+        true
+    }
+
+    fn name() -> &'static str {
+        "load"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_dumped_lines() {
+        assert_eq!(
+            parse_line("key ==> value ==> more", false),
+            Some(Record {
+                column_family: None,
+                key: "key",
+                value: "value ==> more",
+            })
+        );
+        assert_eq!(
+            parse_line("cf ==> key ==> ", true),
+            Some(Record {
+                column_family: Some("cf"),
+                key: "key",
+                value: "",
+            })
+        );
+        assert_eq!(parse_line("key value", false), None);
+        assert_eq!(parse_line("cf ==> key", true), None);
+    }
+}
@@ -3,14 +3,28 @@ use std::boxed::Box;
 use std::error::Error;
 
 pub mod batchput;
+pub mod cfstats;
 pub mod checkconsistency;
+pub mod count;
+pub mod createcf;
+pub mod database;
 pub mod delete;
 pub mod deleterange;
+pub mod dropcf;
 pub mod dump;
+pub mod filter;
 pub mod get;
+pub mod listcf;
+pub mod load;
+pub mod properties;
 pub mod put;
+pub mod query;
+pub mod range;
 pub mod scan;
+pub mod shell;
+pub mod stats;
 pub mod traits;
+pub mod tui;
 
 pub fn create<'a>(
     matches: &'a ArgMatches<'a>,
@@ -24,6 +38,16 @@ pub fn create<'a>(
         ("deleterange", Some(_)) => deleterange::DeleteRange::create(matches),
         ("checkconsistency", Some(_)) => checkconsistency::CheckConsistency::create(matches),
         ("dump", Some(_)) => dump::Dump::create(matches),
+        ("listcf", Some(_)) => listcf::ListCf::create(matches),
+        ("createcf", Some(_)) => createcf::CreateCf::create(matches),
+        ("dropcf", Some(_)) => dropcf::DropCf::create(matches),
+        ("cfstats", Some(_)) => cfstats::CfStats::create(matches),
+        ("count", Some(_)) => count::Count::create(matches),
+        ("stats", Some(_)) => stats::Stats::create(matches),
+        ("properties", Some(_)) => properties::Properties::create(matches),
+        ("load", Some(_)) => load::Load::create(matches),
+        ("shell", Some(_)) => shell::Shell::create(matches),
+        ("tui", Some(_)) => tui::Tui::create(matches),
         _ => unreachable!(),
     }
 }
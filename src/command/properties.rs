@@ -0,0 +1,199 @@
+use crate::command::database;
+use crate::command::traits::Command;
+use crate::printer::OutputFormat;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use rocksdb::DB;
+use serde_json::{Map, Value};
+use simple_error::SimpleError;
+use std::boxed::Box;
+use std::error::Error;
+use std::io::{self, Write};
+
+/// Multi-line reports of the DB internals
+const TEXT_PROPERTIES: &[&str] = &["rocksdb.stats", "rocksdb.levelstats", "rocksdb.sstables"];
+
+/// Counters and sizes helping to diagnose memtables, compactions and stalls
+const INT_PROPERTIES: &[&str] = &[
+    "rocksdb.num-live-versions",
+    "rocksdb.num-snapshots",
+    "rocksdb.estimate-num-keys",
+    "rocksdb.estimate-live-data-size",
+    "rocksdb.total-sst-files-size",
+    "rocksdb.live-sst-files-size",
+    "rocksdb.num-immutable-mem-table",
+    "rocksdb.num-entries-active-mem-table",
+    "rocksdb.num-entries-imm-mem-tables",
+    "rocksdb.cur-size-active-mem-table",
+    "rocksdb.cur-size-all-mem-tables",
+    "rocksdb.size-all-mem-tables",
+    "rocksdb.mem-table-flush-pending",
+    "rocksdb.num-running-flushes",
+    "rocksdb.compaction-pending",
+    "rocksdb.num-running-compactions",
+    "rocksdb.estimate-pending-compaction-bytes",
+    "rocksdb.estimate-table-readers-mem",
+    "rocksdb.block-cache-usage",
+    "rocksdb.background-errors",
+    "rocksdb.actual-delayed-write-rate",
+    "rocksdb.is-write-stopped",
+];
+
+/// A property of a column family, `None` if RocksDB does not know it
+#[derive(Debug)]
+enum Property {
+    Int(Option<u64>),
+    Text(Option<String>),
+}
+
+impl Property {
+    fn to_json(&self) -> Value {
+        match self {
+            Property::Int(value) => value.map_or(Value::Null, Value::from),
+            Property::Text(value) => value.clone().map_or(Value::Null, Value::String),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Properties {
+    db: DB,
+    column_families: Vec<String>,
+    /// Properties given with `--property`, all the known ones by default
+    names: Option<Vec<String>>,
+    /// Plain text, or JSON objects by column family
+    output_format: OutputFormat,
+}
+
+impl Properties {
+    pub fn create<'a>(
+        matches: &'a ArgMatches<'a>,
+    ) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
+        let subcommand_matches = matches.subcommand_matches(Self::name()).unwrap();
+        let output_format = match matches.value_of("output_format").unwrap().parse()? {
+            _ if subcommand_matches.is_present("json") => OutputFormat::Json,
+            format @ (OutputFormat::Plain | OutputFormat::Json | OutputFormat::Jsonl) => format,
+            format => {
+                return Err(Box::new(SimpleError::new(format!(
+                    "Properties can not be printed as {}",
+                    format.name()
+                ))))
+            }
+        };
+        let mut opts = Self::build_options(matches);
+        let db = Self::open_db(matches, &mut opts)?;
+        let column_families = database::target_column_families(matches, subcommand_matches);
+        Ok(std::boxed::Box::new(Properties {
+            db,
+            column_families,
+            names: subcommand_matches
+                .values_of("property")
+                .map(|names| names.map(String::from).collect()),
+            output_format,
+        }))
+    }
+
+    /// The properties of the column family in the order they are printed, counters first
+    fn properties(&self, name: &str) -> Result<Vec<(String, Property)>, Box<dyn Error>> {
+        let cf = database::cf_handle(&self.db, name)?;
+        let mut properties = Vec::new();
+        match self.names {
+            // Any property can be read as text, integer ones are typed as the default ones are.
+            // `property_int_value_cf` fails on text values, so the text is parsed instead.
+            Some(ref names) => {
+                for property in names {
+                    let value = self.db.property_value_cf(cf, property)?;
+                    let value = match value.as_deref().map(str::parse::<u64>) {
+                        Some(Ok(value)) => Property::Int(Some(value)),
+                        _ => Property::Text(value),
+                    };
+                    properties.push((property.clone(), value));
+                }
+            }
+            None => {
+                for property in INT_PROPERTIES {
+                    let value = self.db.property_int_value_cf(cf, property)?;
+                    properties.push((property.to_string(), Property::Int(value)));
+                }
+                for property in TEXT_PROPERTIES {
+                    let value = self.db.property_value_cf(cf, property)?;
+                    properties.push((property.to_string(), Property::Text(value)));
+                }
+            }
+        }
+        Ok(properties)
+    }
+}
+
+impl Command for Properties {
+    fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        let mut reports = Map::new();
+        for (i, name) in self.column_families.iter().enumerate() {
+            let properties = self.properties(name)?;
+            if self.output_format != OutputFormat::Plain {
+                let properties = properties
+                    .iter()
+                    .map(|(property, value)| (property.clone(), value.to_json()))
+                    .collect();
+                if self.output_format == OutputFormat::Jsonl {
+                    // One line of the object printed as JSON
+                    let mut report = Map::new();
+                    report.insert(name.clone(), Value::Object(properties));
+                    writeln!(out, "{}", Value::Object(report))?;
+                } else {
+                    reports.insert(name.clone(), Value::Object(properties));
+                }
+                continue;
+            }
+            if i > 0 {
+                writeln!(out)?;
+            }
+            writeln!(out, "Column family: {}", name)?;
+            for (property, value) in properties {
+                match value {
+                    Property::Int(Some(value)) => writeln!(out, "{} : {}", property, value)?,
+                    Property::Text(Some(value)) if value.contains('\n') => {
+                        writeln!(out, "{} :\n{}", property, value.trim_end())?
+                    }
+                    Property::Text(Some(value)) => writeln!(out, "{} : {}", property, value)?,
+                    Property::Int(None) | Property::Text(None) => {
+                        writeln!(out, "{} : n/a", property)?
+                    }
+                }
+            }
+        }
+        if self.output_format == OutputFormat::Json {
+            serde_json::to_writer_pretty(&mut out, &reports)?;
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    fn args() -> App<'static, 'static> {
+        SubCommand::with_name(Self::name())
+            .about("Prints RocksDB properties and internal statistics of column families")
+            .arg(database::all_column_families_arg())
+            .arg(
+                Arg::with_name("property")
+                    .long("property")
+                    .value_name("NAME")
+                    .help("Property to print instead of the default ones, e.g. rocksdb.num-files-at-level0, may be repeated")
+                    .required(false)
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1),
+            )
+            .arg(
+                Arg::with_name("json")
+                    .long("json")
+                    .help("Prints the properties as a JSON object by column family")
+                    .required(false)
+                    .takes_value(false),
+            )
+    }
+
+    fn name() -> &'static str {
+        "properties"
+    }
+}
@@ -1,53 +1,55 @@
+use crate::command::database;
 use crate::command::traits::Command;
-use crate::utils;
+use crate::printer::Encoding;
+use crate::utils::codec;
 use clap::{App, Arg, ArgMatches, SubCommand};
 use rocksdb::DB;
 use std::boxed::Box;
 use std::error::Error;
+use std::rc::Rc;
 
 #[derive(Debug)]
 pub struct Put<'a> {
-    db: DB,
+    db: Rc<DB>,
+    column_family: &'a str,
     key: &'a str,
     value: &'a str,
-    key_hex: bool,
-    value_hex: bool,
+    encoding: Encoding,
 }
 
 impl<'a> Put<'a> {
     pub fn create(matches: &'a ArgMatches<'a>) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
-        let opts = Self::build_options(matches);
-        let db = DB::open_cf(
-            &opts,
-            matches.value_of("db").unwrap(),
-            &[matches.value_of("column_family").unwrap()],
-        )?;
+        let mut opts = Self::build_options(matches);
+        let db = Self::open_db(matches, &mut opts)?;
         let subcommand_matches = matches.subcommand_matches(Self::name()).unwrap();
+        Self::with_db(Rc::new(db), matches, subcommand_matches)
+    }
+
+    /// Creates the command operating on an already opened DB
+    pub fn with_db(
+        db: Rc<DB>,
+        matches: &'a ArgMatches<'a>,
+        subcommand_matches: &'a ArgMatches<'a>,
+    ) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
         Ok(std::boxed::Box::new(Put {
             db,
+            column_family: database::column_family(matches, Self::name())?,
             key: subcommand_matches.value_of("KEY").unwrap(),
             value: subcommand_matches.value_of("VALUE").unwrap(),
-            key_hex: subcommand_matches.is_present("key_hex")
-                || subcommand_matches.is_present("hex"),
-            value_hex: subcommand_matches.is_present("value_hex")
-                || subcommand_matches.is_present("hex"),
+            encoding: Encoding::from_matches(matches, subcommand_matches)?,
         }))
     }
 }
 
 impl<'a> Command for Put<'a> {
     fn run(&mut self) -> Result<(), Box<dyn Error>> {
-        let key = if self.key_hex {
-            utils::hex::decode(&self.key)?
-        } else {
-            self.key.as_bytes().into()
-        };
-        let value = if self.value_hex {
-            utils::hex::decode(&self.value)?
-        } else {
-            self.value.as_bytes().into()
-        };
-        self.db.put(key, value)?;
+        let cf = database::cf_handle(&self.db, self.column_family)?;
+        let column_family = Some(self.column_family);
+        let key = self.encoding.decode_key(column_family, self.key)?;
+        let value = self
+            .encoding
+            .decode_value(column_family, &key, self.value)?;
+        self.db.put_cf(cf, key, value)?;
         println!("OK");
         Ok(())
     }
@@ -55,27 +57,9 @@ impl<'a> Command for Put<'a> {
     fn args() -> App<'static, 'static> {
         SubCommand::with_name(Self::name())
             .about("Puts given key value to the DB")
-            .arg(
-                Arg::with_name("hex")
-                    .long("hex")
-                    .help("Key and value provided in hex format")
-                    .required(false)
-                    .takes_value(false),
-            )
-            .arg(
-                Arg::with_name("value_hex")
-                    .long("value_hex")
-                    .help("Value provided in hex format")
-                    .required(false)
-                    .takes_value(false),
-            )
-            .arg(
-                Arg::with_name("key_hex")
-                    .long("key_hex")
-                    .help("Key provided in hex format")
-                    .required(false)
-                    .takes_value(false),
-            )
+            .arg(codec::hex_arg())
+            .args(&codec::key_args())
+            .args(&codec::value_args())
             .arg(
                 Arg::with_name("KEY")
                     .help("Value to put")
@@ -90,6 +74,10 @@ impl<'a> Command for Put<'a> {
             )
     }
 
+    fn modifies_db() -> bool {
+        true
+    }
+
     fn name() -> &'static str {
         "put"
     }
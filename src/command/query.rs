@@ -0,0 +1,561 @@
+use crate::printer::Encoding;
+use clap::{Arg, ArgMatches};
+use serde_json::{json, Value};
+use simple_error::SimpleError;
+use std::boxed::Box;
+use std::cmp::Ordering;
+use std::error::Error;
+
+/// Fields of a record that paths start from
+const ROOTS: [&str; 3] = ["key", "value", "column_family"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(String),
+    Str(String),
+    Op(&'static str),
+}
+
+const OPERATORS: [&str; 15] = [
+    "==", "!=", "<=", ">=", "&&", "||", "<", ">", "!", "(", ")", ".", "[", "]", ",",
+];
+
+fn error<T>(position: usize, message: &str) -> Result<T, Box<dyn Error>> {
+    Err(Box::new(SimpleError::new(format!(
+        "Invalid query at position {}: {}",
+        position, message
+    ))))
+}
+
+/// Splits the query into tokens along with their byte positions
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, Box<dyn Error>> {
+    let mut tokens: Vec<(usize, Token)> = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some(&(position, c)) = chars.peek() {
+        let rest = &text[position..];
+        let take_while =
+            |predicate: fn(char) -> bool| rest.find(|c| !predicate(c)).unwrap_or(rest.len());
+        let after_dot = matches!(tokens.last(), Some((_, Token::Op("."))));
+        let (length, token) = if c.is_whitespace() {
+            chars.next();
+            continue;
+        } else if after_dot || c.is_ascii_alphabetic() || c == '_' {
+            // Fields after a dot may be indexes of arrays, e.g. `key.0`
+            let length = take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+            if length == 0 {
+                return error(position, "expected a field name");
+            }
+            (length, Token::Ident(rest[..length].to_string()))
+        } else if c.is_ascii_digit()
+            || (c == '-' && rest[1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            let length = 1 + rest[1..]
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '+' || c == '-'))
+                .unwrap_or(rest.len() - 1);
+            let number = &rest[..length];
+            match number.parse::<f64>() {
+                Ok(_) => (length, Token::Number(number.to_string())),
+                Err(_) => return error(position, &format!("invalid number {}", number)),
+            }
+        } else if c == '"' || c == '\'' {
+            let mut string = String::new();
+            let mut length = None;
+            let mut escaped = false;
+            for (i, next) in rest.char_indices().skip(1) {
+                if escaped {
+                    string.push(match next {
+                        'n' => '\n',
+                        't' => '\t',
+                        other => other,
+                    });
+                    escaped = false;
+                } else if next == '\\' {
+                    escaped = true;
+                } else if next == c {
+                    length = Some(i + 1);
+                    break;
+                } else {
+                    string.push(next);
+                }
+            }
+            match length {
+                Some(length) => (length, Token::Str(string)),
+                None => return error(position, "unterminated string"),
+            }
+        } else {
+            match OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+                Some(op) => (op.len(), Token::Op(op)),
+                None => return error(position, &format!("unexpected character {}", c)),
+            }
+        };
+        tokens.push((position, token));
+        while chars.peek().is_some_and(|&(i, _)| i < position + length) {
+            chars.next();
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Field(String),
+    Index(usize),
+}
+
+/// Path to a part of a record, e.g. `value.address.city` or `key[1]`
+#[derive(Debug, Clone, PartialEq)]
+struct Path {
+    text: String,
+    segments: Vec<Segment>,
+}
+
+impl Path {
+    fn get<'v>(&self, record: &'v Value) -> Option<&'v Value> {
+        self.segments
+            .iter()
+            .try_fold(record, |value, segment| match (segment, value) {
+                (Segment::Field(name), Value::Object(object)) => object.get(name),
+                // Fields of arrays are indexes, so key tuples read as `key.0`
+                (Segment::Field(name), Value::Array(array)) => {
+                    array.get(name.parse::<usize>().ok()?)
+                }
+                (Segment::Index(index), Value::Array(array)) => array.get(*index),
+                (Segment::Index(index), Value::Object(object)) => object.get(&index.to_string()),
+                _ => None,
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Literal(Value),
+    Path(Path),
+    Compare(Box<Expr>, &'static str, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => {
+            if let (Some(left), Some(right)) = (left.as_i64(), right.as_i64()) {
+                Some(left.cmp(&right))
+            } else if let (Some(left), Some(right)) = (left.as_u64(), right.as_u64()) {
+                Some(left.cmp(&right))
+            } else {
+                left.as_f64()?.partial_cmp(&right.as_f64()?)
+            }
+        }
+        (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+        (Value::Bool(left), Value::Bool(right)) => Some(left.cmp(right)),
+        (Value::Null, Value::Null) => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+fn is_true(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+impl Expr {
+    fn eval(&self, record: &Value) -> Value {
+        match self {
+            Expr::Literal(value) => value.clone(),
+            Expr::Path(path) => path.get(record).cloned().unwrap_or(Value::Null),
+            Expr::Compare(left, op, right) => {
+                let (left, right) = (left.eval(record), right.eval(record));
+                let ordering = compare(&left, &right);
+                Value::Bool(match *op {
+                    // Arrays and objects have no order but may be equal
+                    "==" => ordering.map_or(left == right, Ordering::is_eq),
+                    "!=" => !ordering.map_or(left == right, Ordering::is_eq),
+                    "<" => ordering.is_some_and(Ordering::is_lt),
+                    "<=" => ordering.is_some_and(Ordering::is_le),
+                    ">" => ordering.is_some_and(Ordering::is_gt),
+                    ">=" => ordering.is_some_and(Ordering::is_ge),
+                    _ => unreachable!(),
+                })
+            }
+            Expr::And(left, right) => {
+                Value::Bool(is_true(&left.eval(record)) && is_true(&right.eval(record)))
+            }
+            Expr::Or(left, right) => {
+                Value::Bool(is_true(&left.eval(record)) || is_true(&right.eval(record)))
+            }
+            Expr::Not(expr) => Value::Bool(!is_true(&expr.eval(record))),
+        }
+    }
+}
+
+/// Recursive descent parser of expressions:
+///
+/// ```text
+/// or      := and ("||" and)*
+/// and     := not ("&&" not)*
+/// not     := "!" not | compare
+/// compare := operand (("==" | "!=" | "<" | "<=" | ">" | ">=") operand)?
+/// operand := number | string | true | false | null | path | "(" or ")"
+/// path    := ("key" | "value" | "column_family") ("." field | "[" (number | string) "]")*
+/// ```
+struct Parser<'t> {
+    text: &'t str,
+    tokens: Vec<(usize, Token)>,
+    next: usize,
+}
+
+impl<'t> Parser<'t> {
+    fn new(text: &'t str) -> Result<Parser<'t>, Box<dyn Error>> {
+        Ok(Parser {
+            text,
+            tokens: tokenize(text)?,
+            next: 0,
+        })
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.next)
+            .map_or(self.text.len(), |(position, _)| *position)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next).map(|(_, token)| token)
+    }
+
+    fn eat(&mut self, op: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Op(next)) if *next == op) {
+            self.next += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), Box<dyn Error>> {
+        if self.eat(op) {
+            Ok(())
+        } else {
+            error(self.position(), &format!("expected {}", op))
+        }
+    }
+
+    fn end(&self) -> Result<(), Box<dyn Error>> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => error(self.position(), "unexpected trailing input"),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, Box<dyn Error>> {
+        let mut expr = self.and()?;
+        while self.eat("||") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, Box<dyn Error>> {
+        let mut expr = self.not()?;
+        while self.eat("&&") {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, Box<dyn Error>> {
+        if self.eat("!") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        let left = self.operand()?;
+        let op = match self.peek() {
+            Some(Token::Op(op)) if ["==", "!=", "<", "<=", ">", ">="].contains(op) => *op,
+            _ => return Ok(left),
+        };
+        self.next += 1;
+        Ok(Expr::Compare(Box::new(left), op, Box::new(self.operand()?)))
+    }
+
+    fn operand(&mut self) -> Result<Expr, Box<dyn Error>> {
+        let position = self.position();
+        let literal = match self.peek() {
+            Some(Token::Number(text)) => match serde_json::from_str(text) {
+                Ok(number) => number,
+                Err(_) => return error(position, &format!("invalid number {}", text)),
+            },
+            Some(Token::Str(string)) => Value::String(string.clone()),
+            Some(Token::Ident(name)) if name == "true" => Value::Bool(true),
+            Some(Token::Ident(name)) if name == "false" => Value::Bool(false),
+            Some(Token::Ident(name)) if name == "null" => Value::Null,
+            Some(Token::Ident(_)) => return Ok(Expr::Path(self.path()?)),
+            Some(Token::Op("(")) => {
+                self.next += 1;
+                let expr = self.or()?;
+                self.expect(")")?;
+                return Ok(expr);
+            }
+            _ => return error(position, "expected a value or a path"),
+        };
+        self.next += 1;
+        Ok(Expr::Literal(literal))
+    }
+
+    fn path(&mut self) -> Result<Path, Box<dyn Error>> {
+        let start = self.position();
+        let mut segments = Vec::new();
+        match self.peek() {
+            Some(Token::Ident(root)) if ROOTS.contains(&root.as_str()) => {
+                segments.push(Segment::Field(root.clone()));
+                self.next += 1;
+            }
+            _ => {
+                return error(
+                    start,
+                    "expected a path starting with key, value or column_family",
+                )
+            }
+        }
+        loop {
+            if self.eat(".") {
+                match self.peek() {
+                    Some(Token::Ident(name)) => segments.push(Segment::Field(name.clone())),
+                    _ => return error(self.position(), "expected a field name"),
+                }
+                self.next += 1;
+            } else if self.eat("[") {
+                let position = self.position();
+                match self.peek() {
+                    Some(Token::Number(text)) => match text.parse::<usize>() {
+                        Ok(index) => segments.push(Segment::Index(index)),
+                        Err(_) => return error(position, &format!("invalid index {}", text)),
+                    },
+                    Some(Token::Str(name)) => segments.push(Segment::Field(name.clone())),
+                    _ => return error(position, "expected an index or a field name"),
+                }
+                self.next += 1;
+                self.expect("]")?;
+            } else {
+                break;
+            }
+        }
+        let end = self.position();
+        Ok(Path {
+            text: self.text[start..end].trim().to_string(),
+            segments,
+        })
+    }
+}
+
+/// Condition given with `--where` and projection given with `--select`, both over the record
+/// `{"key": ..., "value": ..., "column_family": ...}` of keys and values decoded by their codecs
+#[derive(Debug, Default)]
+pub struct Query {
+    condition: Option<Expr>,
+    selection: Option<Vec<Path>>,
+}
+
+impl Query {
+    pub fn parse(
+        condition: Option<&str>,
+        selection: Option<&str>,
+    ) -> Result<Query, Box<dyn Error>> {
+        let condition = match condition {
+            Some(text) => {
+                let mut parser = Parser::new(text)?;
+                let expr = parser.or()?;
+                parser.end()?;
+                Some(expr)
+            }
+            None => None,
+        };
+        let selection = match selection {
+            Some(text) => {
+                let mut parser = Parser::new(text)?;
+                let mut paths = vec![parser.path()?];
+                while parser.eat(",") {
+                    paths.push(parser.path()?);
+                }
+                parser.end()?;
+                Some(paths)
+            }
+            None => None,
+        };
+        Ok(Query {
+            condition,
+            selection,
+        })
+    }
+
+    pub fn from_matches(subcommand_matches: &ArgMatches) -> Result<Query, Box<dyn Error>> {
+        Query::parse(
+            subcommand_matches.value_of("where"),
+            subcommand_matches.value_of("select"),
+        )
+    }
+
+    /// Whether a condition is given
+    pub fn is_active(&self) -> bool {
+        self.condition.is_some()
+    }
+
+    /// Whether a projection is given
+    pub fn is_projection(&self) -> bool {
+        self.selection.is_some()
+    }
+
+    fn record(&self, encoding: &Encoding, column_family: &str, key: &[u8], value: &[u8]) -> Value {
+        json!({
+            "key": encoding.key_json(Some(column_family), key),
+            "value": encoding.value_json(Some(column_family), key, value),
+            "column_family": column_family,
+        })
+    }
+
+    pub fn matches(
+        &self,
+        encoding: &Encoding,
+        column_family: &str,
+        key: &[u8],
+        value: &[u8],
+    ) -> bool {
+        match self.condition {
+            Some(ref condition) => {
+                is_true(&condition.eval(&self.record(encoding, column_family, key, value)))
+            }
+            None => true,
+        }
+    }
+
+    /// The selected paths of the record as a JSON object keyed by path in the order they are
+    /// given, `None` without `--select`
+    pub fn select(
+        &self,
+        encoding: &Encoding,
+        column_family: &str,
+        key: &[u8],
+        value: &[u8],
+    ) -> Option<Vec<u8>> {
+        let selection = self.selection.as_ref()?;
+        let record = self.record(encoding, column_family, key, value);
+        // JSON maps sort their keys, so the object is written by hand
+        let fields: Vec<String> = selection
+            .iter()
+            .map(|path| {
+                format!(
+                    "{}:{}",
+                    Value::String(path.text.clone()),
+                    path.get(&record).unwrap_or(&Value::Null)
+                )
+            })
+            .collect();
+        Some(format!("{{{}}}", fields.join(",")).into_bytes())
+    }
+}
+
+/// `--where` and `--select` of commands iterating over keys
+pub fn args() -> [Arg<'static, 'static>; 2] {
+    [
+        Arg::with_name("where")
+            .long("where")
+            .value_name("EXPR")
+            .help("Only records matching the expression over the decoded key and value, e.g. 'key.user_id > 1000 && value.status == \"active\"'")
+            .required(false)
+            .takes_value(true),
+        Arg::with_name("select")
+            .long("select")
+            .value_name("PATHS")
+            .help("Prints the comma separated paths of the decoded record as JSON instead of the value, e.g. 'value.name,value.address.city'")
+            .required(false)
+            .takes_value(true),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(condition: &str, record: &Value) -> bool {
+        let query = Query::parse(Some(condition), None).unwrap();
+        is_true(&query.condition.unwrap().eval(record))
+    }
+
+    #[test]
+    fn test_conditions() {
+        let record = json!({
+            "key": {"tenant": 1, "user_id": 1500},
+            "value": {"status": "active", "score": -2.5, "tags": ["a", "b"], "deleted": false},
+            "column_family": "users",
+        });
+        assert!(eval(
+            "key.user_id > 1000 && value.status == \"active\"",
+            &record
+        ));
+        assert!(!eval(
+            "key.user_id > 1000 && value.status == 'inactive'",
+            &record
+        ));
+        assert!(eval("key.user_id >= 1500 || value.missing", &record));
+        assert!(eval("!(key.user_id < 1500) && value.score < -2", &record));
+        assert!(eval("value.score == -2.5 && key.tenant == 1.0", &record));
+        assert!(eval("value.tags[1] == 'b' && value.tags.0 == 'a'", &record));
+        assert!(eval("value[\"status\"] != 'deleted'", &record));
+        assert!(eval("!value.deleted && value.missing == null", &record));
+        assert!(eval("column_family == 'users'", &record));
+        // Values of different types are neither equal nor ordered
+        assert!(!eval("key.user_id == '1500'", &record));
+        assert!(!eval("key.user_id < 'a'", &record));
+        assert!(eval("key.user_id != '1500'", &record));
+
+        let record = json!({"key": [1, 18446744073709551615u64], "value": "text"});
+        assert!(eval("key.1 > 1 && key[0] > -1", &record));
+        assert!(eval("value >= 'te'", &record));
+    }
+
+    #[test]
+    fn test_invalid_queries() {
+        let message = |condition| Query::parse(Some(condition), None).unwrap_err().to_string();
+        assert_eq!(
+            message("key.a == "),
+            "Invalid query at position 9: expected a value or a path"
+        );
+        assert_eq!(
+            message("user.id == 1"),
+            "Invalid query at position 0: expected a path starting with key, value or column_family"
+        );
+        assert_eq!(
+            message("value == 'a"),
+            "Invalid query at position 9: unterminated string"
+        );
+        assert_eq!(
+            message("(key == 1"),
+            "Invalid query at position 9: expected )"
+        );
+        assert_eq!(
+            message("key == 1 value"),
+            "Invalid query at position 9: unexpected trailing input"
+        );
+        assert!(Query::parse(Some("key = 1"), None).is_err());
+        assert!(Query::parse(None, Some("value.a,")).is_err());
+    }
+
+    #[test]
+    fn test_selection() {
+        let query = Query::parse(None, Some("value.name, key[0],value.address.city")).unwrap();
+        let record = json!({"key": [7], "value": {"name": "bob", "address": {}}});
+        let selection: Vec<_> = query
+            .selection
+            .unwrap()
+            .iter()
+            .map(|path| (path.text.clone(), path.get(&record).cloned()))
+            .collect();
+        assert_eq!(
+            selection,
+            vec![
+                ("value.name".to_string(), Some(json!("bob"))),
+                ("key[0]".to_string(), Some(json!(7))),
+                ("value.address.city".to_string(), None),
+            ]
+        );
+    }
+}
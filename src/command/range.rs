@@ -0,0 +1,301 @@
+use clap::{Arg, ArgMatches};
+use rocksdb::{Direction, IteratorMode, Options, ReadOptions, SliceTransform};
+use simple_error::SimpleError;
+use std::boxed::Box;
+use std::error::Error;
+
+/// Keys from `start` inclusive to `end` exclusive, unbounded where not given
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeyRange {
+    pub start: Option<Vec<u8>>,
+    pub end: Option<Vec<u8>>,
+    /// Prefix shared by all the keys of a prefix range
+    pub prefix: Option<Vec<u8>>,
+}
+
+impl KeyRange {
+    pub fn new(start: Option<Vec<u8>>, end: Option<Vec<u8>>, inclusive_end: bool) -> KeyRange {
+        KeyRange {
+            start,
+            end: if inclusive_end {
+                end.map(|end| key_successor(&end))
+            } else {
+                end
+            },
+            prefix: None,
+        }
+    }
+
+    /// Keys starting with the prefix
+    pub fn prefix(prefix: Vec<u8>) -> KeyRange {
+        KeyRange {
+            start: Some(prefix.clone()),
+            end: prefix_end(&prefix),
+            prefix: Some(prefix),
+        }
+    }
+
+    /// The range given with `--from`, `--to`, `--inclusive` or `--prefix`, keys are typed as
+    /// the decoder expects
+    pub fn from_matches<F>(
+        subcommand_matches: &ArgMatches,
+        decode: F,
+    ) -> Result<KeyRange, Box<dyn Error>>
+    where
+        F: Fn(&str) -> Result<Vec<u8>, Box<dyn Error>>,
+    {
+        if let Some(prefix) = subcommand_matches.value_of("prefix") {
+            return Ok(KeyRange::prefix(decode(prefix)?));
+        }
+        let decode_bound = |name| match subcommand_matches.value_of(name) {
+            Some(key) => decode(key).map(Some),
+            None => Ok(None),
+        };
+        Ok(KeyRange::new(
+            decode_bound("from")?,
+            decode_bound("to")?,
+            subcommand_matches.is_present("inclusive"),
+        ))
+    }
+
+    /// The part of the range from `start` to `end`, so a range can be split at inner keys
+    pub fn slice(&self, start: Option<Vec<u8>>, end: Option<Vec<u8>>) -> KeyRange {
+        KeyRange {
+            start,
+            end,
+            prefix: self.prefix.clone(),
+        }
+    }
+
+    /// The rest of the range after the key, which is the last one seen in the direction
+    pub fn after(&self, key: &[u8], reverse: bool) -> KeyRange {
+        if reverse {
+            self.slice(self.start.clone(), Some(key.to_vec()))
+        } else {
+            self.slice(Some(key_successor(key)), self.end.clone())
+        }
+    }
+
+    pub fn contains(&self, key: &[u8]) -> bool {
+        self.start.as_deref().is_none_or(|start| key >= start) && !self.is_past_end(key)
+    }
+
+    pub fn is_past_end(&self, key: &[u8]) -> bool {
+        self.end.as_deref().is_some_and(|end| key >= end)
+    }
+
+    /// Iterator mode seeking to the start of the range
+    pub fn mode(&self) -> IteratorMode<'_> {
+        match self.start {
+            Some(ref start) => IteratorMode::From(start, Direction::Forward),
+            None => IteratorMode::Start,
+        }
+    }
+
+    /// Iterator mode seeking to the end of the range backwards, the upper bound makes RocksDB
+    /// seek to the last key before the end
+    pub fn reverse_mode(&self) -> IteratorMode<'_> {
+        IteratorMode::End
+    }
+
+    /// Read options bounding the iteration, so RocksDB does not read past the end of the range.
+    ///
+    /// Prefix ranges also stop at the end of the extracted prefix, which must not be longer
+    /// than the prefix itself, otherwise keys of the prefix would be missed. Reverse iteration
+    /// seeks before the upper bound, whose prefix is not the range's one, so it is bounded by
+    /// the range alone and seeks in total order.
+    pub fn read_options(
+        &self,
+        prefix_length: Option<usize>,
+        reverse: bool,
+    ) -> Result<ReadOptions, Box<dyn Error>> {
+        let mut readopts = ReadOptions::default();
+        if let Some(ref start) = self.start {
+            readopts.set_iterate_lower_bound(start.clone());
+        }
+        if let Some(ref end) = self.end {
+            readopts.set_iterate_upper_bound(end.clone());
+        }
+        if let Some(ref prefix) = self.prefix {
+            if let Some(length) = prefix_length {
+                if prefix.len() < length {
+                    return Err(Box::new(SimpleError::new(format!(
+                        "Prefix of {} bytes is shorter than the prefix extractor length {}",
+                        prefix.len(),
+                        length
+                    ))));
+                }
+            }
+            if reverse {
+                readopts.set_total_order_seek(true);
+            } else {
+                readopts.set_prefix_same_as_start(true);
+            }
+        }
+        Ok(readopts)
+    }
+}
+
+/// The first key after the given one
+fn key_successor(key: &[u8]) -> Vec<u8> {
+    let mut successor = key.to_vec();
+    successor.push(0);
+    successor
+}
+
+/// The first key after all the keys starting with the prefix, `None` if there is no such key
+pub fn prefix_end(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return Some(end);
+        }
+    }
+    None
+}
+
+/// `--from`, `--to`, `--inclusive` and `--prefix` of range commands
+pub fn args() -> [Arg<'static, 'static>; 4] {
+    [
+        Arg::with_name("from")
+            .long("from")
+            .help("Key to start from")
+            .required(false)
+            .takes_value(true),
+        Arg::with_name("to")
+            .long("to")
+            .help("Key to stop at, excluded unless --inclusive is given")
+            .required(false)
+            .takes_value(true),
+        inclusive_arg().requires("to"),
+        prefix_arg().conflicts_with_all(&["from", "to"]),
+    ]
+}
+
+pub fn inclusive_arg() -> Arg<'static, 'static> {
+    Arg::with_name("inclusive")
+        .long("inclusive")
+        .help("Includes the end key in the range")
+        .required(false)
+        .takes_value(false)
+}
+
+pub fn prefix_arg() -> Arg<'static, 'static> {
+    Arg::with_name("prefix")
+        .long("prefix")
+        .help("Only keys starting with the prefix")
+        .required(false)
+        .takes_value(true)
+}
+
+pub fn prefix_length_arg() -> Arg<'static, 'static> {
+    Arg::with_name("prefix_length")
+        .long("prefix-length")
+        .value_name("LENGTH")
+        .help("Configures a fixed length prefix extractor, so the prefix seek can use prefix bloom filters, in read-write mode only")
+        .required(false)
+        .takes_value(true)
+        .requires("prefix")
+}
+
+/// Length of the prefix extractor given with `--prefix-length`
+pub fn prefix_length(subcommand_matches: &ArgMatches) -> Result<Option<usize>, Box<dyn Error>> {
+    match subcommand_matches.value_of("prefix_length") {
+        Some(length) => Ok(Some(length.parse::<usize>()?)),
+        None => Ok(None),
+    }
+}
+
+/// Configures the prefix extractor given with `--prefix-length`
+pub fn set_prefix_extractor(
+    opts: &mut Options,
+    subcommand_matches: &ArgMatches,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(length) = prefix_length(subcommand_matches)? {
+        opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(length));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: &str, end: &str, inclusive_end: bool) -> KeyRange {
+        KeyRange::new(
+            Some(start.as_bytes().to_vec()),
+            Some(end.as_bytes().to_vec()),
+            inclusive_end,
+        )
+    }
+
+    #[test]
+    fn test_exclusive_end() {
+        let range = range("ab", "abc", false);
+        assert!(!range.contains(b"a"));
+        assert!(range.contains(b"ab"));
+        assert!(range.contains(b"abb"));
+        assert!(range.contains(b"abbz"));
+        assert!(!range.contains(b"abc"));
+        // Longer keys sharing the bound as a prefix are past it
+        assert!(!range.contains(b"abcd"));
+        assert!(!range.contains(b"abd"));
+        assert!(!range.is_past_end(b"a"));
+        assert!(range.is_past_end(b"abc"));
+    }
+
+    #[test]
+    fn test_inclusive_end() {
+        let range = range("ab", "abc", true);
+        assert_eq!(range.end.as_deref(), Some(&b"abc\x00"[..]));
+        assert!(range.contains(b"abc"));
+        assert!(!range.contains(b"abc\x00"));
+        assert!(!range.contains(b"abcd"));
+    }
+
+    #[test]
+    fn test_unbounded() {
+        let range = KeyRange::new(None, Some(b"b".to_vec()), false);
+        assert!(range.contains(b""));
+        assert!(range.contains(b"a\xff"));
+        assert!(!range.contains(b"b"));
+        let range = KeyRange::default();
+        assert!(range.contains(b"\xff\xff"));
+    }
+
+    #[test]
+    fn test_prefix() {
+        let range = KeyRange::prefix(b"ab".to_vec());
+        assert!(!range.contains(b"a"));
+        assert!(range.contains(b"ab"));
+        assert!(range.contains(b"ab\xff\xff"));
+        assert!(!range.contains(b"ac"));
+        assert_eq!(KeyRange::prefix(b"a\xff".to_vec()).end, Some(b"b".to_vec()));
+        let range = KeyRange::prefix(b"\xff".to_vec());
+        assert_eq!(range.end, None);
+        assert!(range.contains(b"\xff\xff"));
+        assert!(!range.contains(b"\xfe"));
+    }
+
+    #[test]
+    fn test_after() {
+        let range = range("a", "c", false);
+        let rest = range.after(b"ab", false);
+        assert!(!rest.contains(b"ab"));
+        assert!(rest.contains(b"ab\x00"));
+        assert!(!rest.contains(b"c"));
+        let rest = range.after(b"ab", true);
+        assert!(rest.contains(b"a"));
+        assert!(rest.contains(b"aa\xff"));
+        assert!(!rest.contains(b"ab"));
+    }
+
+    #[test]
+    fn test_prefix_end() {
+        assert_eq!(prefix_end(b"ab"), Some(b"ac".to_vec()));
+        assert_eq!(prefix_end(b"a\xff\xff"), Some(b"b".to_vec()));
+        assert_eq!(prefix_end(b"\xff"), None);
+        assert_eq!(prefix_end(b""), None);
+    }
+}
@@ -1,99 +1,260 @@
+use crate::command::database;
+use crate::command::filter::{self, Counts, Filter};
+use crate::command::query::{self, Query};
+use crate::command::range::{self, KeyRange};
 use crate::command::traits::Command;
+use crate::printer::{self, Encoding, OutputFormat};
 use crate::utils;
+use crate::utils::codec::{self, Codec};
 use clap::{App, Arg, ArgMatches, SubCommand};
-use rocksdb::{Direction, IteratorMode, DB};
+use rocksdb::DB;
+use serde_json::{json, Value};
+use simple_error::SimpleError;
 use std::boxed::Box;
-use std::cmp;
+use std::cell::Cell;
 use std::error::Error;
-use std::option::Option;
+use std::rc::Rc;
+use std::str::FromStr;
 
-fn compare(first: &[u8], second: &[u8]) -> i32 {
-    for i in 0..cmp::min(first.len(), second.len()) {
-        //
-        if first[i] < second[i] {
-            return -1;
-        }
-        if first[i] > second[i] {
-            return 1;
-        }
+pub const DELIM: &str = " : ";
+
+/// Key and value as returned by the iterator
+type Record = (Box<[u8]>, Box<[u8]>);
+
+/// Position after the last key of a page, given back with `--cursor` to get the next page
+#[derive(Debug, Clone, PartialEq)]
+struct Cursor {
+    column_family: String,
+    key: Vec<u8>,
+    reverse: bool,
+}
+
+impl Cursor {
+    /// Opaque token printed at the end of a page
+    fn token(&self) -> String {
+        let cursor = json!({
+            "column_family": self.column_family,
+            "key": utils::hex::encode(&self.key),
+            "reverse": self.reverse,
+        });
+        base64::encode_config(cursor.to_string(), base64::URL_SAFE_NO_PAD)
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = SimpleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = || {
+            let data = base64::decode_config(s, base64::URL_SAFE_NO_PAD).ok()?;
+            let cursor: Value = serde_json::from_slice(&data).ok()?;
+            Some(Cursor {
+                column_family: cursor["column_family"].as_str()?.to_string(),
+                key: utils::hex::decode(cursor["key"].as_str()?).ok()?,
+                reverse: cursor["reverse"].as_bool()?,
+            })
+        };
+        parse().ok_or_else(|| SimpleError::new(format!("Invalid cursor: {}", s)))
     }
-    0
 }
 
 #[derive(Debug)]
-pub struct Scan<'a> {
-    db: DB,
-    from: Option<&'a str>,
-    to: Option<&'a str>,
-    key_hex: bool,
-    value_hex: bool,
+pub struct Scan {
+    db: Rc<DB>,
+    column_families: Vec<String>,
+    range: KeyRange,
+    prefix_length: Option<usize>,
+    reverse: bool,
+    /// Number of keys shown from the end of the range
+    last: Option<usize>,
+    limit: Option<usize>,
+    skip: usize,
+    cursor: Option<Cursor>,
+    filter: Filter,
+    query: Query,
+    encoding: Encoding,
+    output_format: OutputFormat,
 }
 
-impl<'a> Scan<'a> {
-    pub fn create(matches: &'a ArgMatches<'a>) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
-        let opts = Self::build_options(matches);
-        let db = DB::open_cf(
-            &opts,
-            matches.value_of("db").unwrap(),
-            &[matches.value_of("column_family").unwrap()],
-        )?;
+impl Scan {
+    pub fn create<'a>(
+        matches: &'a ArgMatches<'a>,
+    ) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
+        let mut opts = Self::build_options(matches);
         let subcommand_matches = matches.subcommand_matches(Self::name()).unwrap();
+        range::set_prefix_extractor(&mut opts, subcommand_matches)?;
+        let db = Self::open_db(matches, &mut opts)?;
+        Self::with_db(Rc::new(db), matches, subcommand_matches)
+    }
+
+    /// Creates the command operating on an already opened DB
+    pub fn with_db<'a>(
+        db: Rc<DB>,
+        matches: &'a ArgMatches<'a>,
+        subcommand_matches: &'a ArgMatches<'a>,
+    ) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
+        let column_families = database::target_column_families(matches, subcommand_matches);
+        let encoding = Encoding::from_matches(matches, subcommand_matches)?;
         Ok(std::boxed::Box::new(Scan {
             db,
-            from: subcommand_matches.value_of("from"),
-            to: subcommand_matches.value_of("to"),
-            key_hex: subcommand_matches.is_present("key_hex")
-                || subcommand_matches.is_present("hex"),
-            value_hex: subcommand_matches.is_present("value_hex")
-                || subcommand_matches.is_present("hex"),
+            column_families,
+            range: KeyRange::from_matches(subcommand_matches, |key| {
+                encoding.decode_key(None, key)
+            })?,
+            prefix_length: range::prefix_length(subcommand_matches)?,
+            reverse: subcommand_matches.is_present("reverse"),
+            last: match subcommand_matches.value_of("last") {
+                Some(last) => Some(last.parse::<usize>()?),
+                None => None,
+            },
+            limit: match subcommand_matches.value_of("limit") {
+                // Nothing would be printed and no cursor to continue from either
+                Some(limit) => match limit.parse::<usize>()? {
+                    0 => return Err(Box::new(SimpleError::new("Limit must be positive"))),
+                    limit => Some(limit),
+                },
+                None => None,
+            },
+            skip: match subcommand_matches.value_of("skip") {
+                Some(skip) => skip.parse::<usize>()?,
+                None => 0,
+            },
+            cursor: match subcommand_matches.value_of("cursor") {
+                Some(cursor) => Some(cursor.parse()?),
+                None => None,
+            },
+            filter: Filter::from_matches(subcommand_matches)?,
+            query: Query::from_matches(subcommand_matches)?,
+            encoding,
+            output_format: matches.value_of("output_format").unwrap().parse()?,
         }))
     }
 }
 
-impl<'a> Command for Scan<'a> {
+impl Command for Scan {
     fn run(&mut self) -> Result<(), Box<dyn Error>> {
-        let iter = match self.from {
-            None => self.db.iterator(IteratorMode::Start),
-            Some(from) => {
-                let f = if self.key_hex {
-                    utils::hex::decode(from)?
-                } else {
-                    Vec::from(from.as_bytes())
-                };
-                self.db
-                    .iterator(IteratorMode::From(f.as_ref(), Direction::Forward))
+        let encoding = if self.query.is_projection() {
+            // Projections are printed as JSON text in place of the values
+            Encoding {
+                value: Some(Codec::Utf8),
+                ..self.encoding.clone()
             }
+        } else {
+            self.encoding.clone()
         };
-        let end = match self.to {
-            None => None,
-            Some(to) => {
-                if self.key_hex {
-                    Some(utils::hex::decode(to)?)
-                } else {
-                    Some(Vec::from(to.as_bytes()))
+        let mut printer = printer::create(
+            self.output_format,
+            Box::new(std::io::stdout()),
+            encoding,
+            Some(DELIM),
+        );
+        let show_column_family = self.column_families.len() > 1;
+        // The last keys are found from the end
+        let reverse = self.reverse || self.last.is_some();
+        // Column families before the one of the cursor are already paged through
+        let first = match self.cursor {
+            None => 0,
+            Some(ref cursor) => {
+                if cursor.reverse != reverse {
+                    return Err(Box::new(SimpleError::new(
+                        "Cursor belongs to a scan in the other direction",
+                    )));
                 }
-            }
-        };
-        for (key, value) in iter {
-            match end {
-                None => {}
-                Some(ref e) => {
-                    if compare(key.as_ref(), e.as_ref()) >= 0 {
-                        break;
+                match self
+                    .column_families
+                    .iter()
+                    .position(|name| *name == cursor.column_family)
+                {
+                    Some(position) => position,
+                    None => {
+                        return Err(Box::new(SimpleError::new(format!(
+                            "Cursor belongs to column family {} which is not scanned",
+                            cursor.column_family
+                        ))))
                     }
                 }
             }
-            let k = if self.key_hex {
-                utils::hex::encode(key.as_ref())
-            } else {
-                String::from_utf8_lossy(key.as_ref()).into()
-            };
-            let v = if self.value_hex {
-                utils::hex::encode(value.as_ref())
-            } else {
-                String::from_utf8_lossy(value.as_ref()).into()
+        };
+        // The skipped keys precede the first page, so scripts may continue with the same flags
+        let skip = if self.cursor.is_some() { 0 } else { self.skip };
+        let mut skipped = 0;
+        let mut printed = 0;
+        // Column family and key of the last printed record
+        let mut last: Option<(&str, Box<[u8]>)> = None;
+        let mut next_cursor = None;
+        let counts = Cell::new(Counts::default());
+        'column_families: for (i, name) in self.column_families.iter().enumerate().skip(first) {
+            let range = match self.cursor {
+                Some(ref cursor) if i == first => self.range.after(&cursor.key, reverse),
+                _ => self.range.clone(),
             };
-            println!("{} : {}", k, v);
+            let range = &range;
+            let cf = database::cf_handle(&self.db, name)?;
+            let iter = self.db.iterator_cf_opt(
+                cf,
+                range.read_options(self.prefix_length, reverse)?,
+                if reverse {
+                    range.reverse_mode()
+                } else {
+                    range.mode()
+                },
+            );
+            let mut records: Box<dyn Iterator<Item = Record>> = Box::new(
+                iter.skip_while(|(key, _)| reverse && range.is_past_end(key))
+                    .take_while(|(key, _)| range.contains(key))
+                    .filter(|(key, value)| {
+                        let mut current = counts.get();
+                        current.examined += 1;
+                        let matches = self.filter.matches(key, value)
+                            && self.query.matches(&self.encoding, name, key, value);
+                        if matches {
+                            current.matched += 1;
+                        }
+                        counts.set(current);
+                        matches
+                    }),
+            );
+            if let Some(last) = self.last {
+                let mut tail: Vec<_> = records.take(last).collect();
+                if !self.reverse {
+                    tail.reverse();
+                }
+                records = Box::new(tail.into_iter());
+            }
+            for (key, value) in records {
+                if skipped < skip {
+                    skipped += 1;
+                    continue;
+                }
+                if Some(printed) == self.limit {
+                    // There is at least one more record, so the page gets a cursor
+                    next_cursor = last.map(|(column_family, key)| Cursor {
+                        column_family: column_family.to_string(),
+                        key: key.to_vec(),
+                        reverse,
+                    });
+                    break 'column_families;
+                }
+                let column_family = if show_column_family {
+                    Some(name.as_str())
+                } else {
+                    None
+                };
+                match self.query.select(&self.encoding, name, &key, &value) {
+                    Some(projection) => printer.print(column_family, key.as_ref(), &projection)?,
+                    None => printer.print(column_family, key.as_ref(), value.as_ref())?,
+                }
+                printed += 1;
+                last = Some((name, key));
+            }
+        }
+        printer.finish()?;
+        if self.filter.is_active() || self.query.is_active() {
+            eprintln!("{}", counts.get());
+        }
+        if let Some(cursor) = next_cursor {
+            // Printed apart from the records, so the output format stays intact
+            eprintln!("Next page: --cursor {}", cursor.token());
         }
         Ok(())
     }
@@ -101,38 +262,51 @@ impl<'a> Command for Scan<'a> {
     fn args() -> App<'static, 'static> {
         SubCommand::with_name(Self::name())
             .about("Prints key : value from the DB")
+            .arg(codec::hex_arg())
+            .args(&codec::key_args())
+            .args(&codec::value_args())
+            .arg(database::all_column_families_arg())
+            .args(&range::args())
+            .arg(range::prefix_length_arg())
+            .args(&filter::args())
+            .args(&query::args())
             .arg(
-                Arg::with_name("hex")
-                    .long("hex")
-                    .help("Key and value provided in hex format")
+                Arg::with_name("reverse")
+                    .long("reverse")
+                    .help("Prints keys from the end of the range backwards")
                     .required(false)
                     .takes_value(false),
             )
             .arg(
-                Arg::with_name("value_hex")
-                    .long("value_hex")
-                    .help("Value provided in hex format")
+                Arg::with_name("last")
+                    .long("last")
+                    .value_name("N")
+                    .help("Prints only the last N keys of the range of each column family")
                     .required(false)
-                    .takes_value(false),
+                    .takes_value(true)
+                    .conflicts_with_all(&["limit", "skip", "cursor"]),
             )
             .arg(
-                Arg::with_name("key_hex")
-                    .long("key_hex")
-                    .help("Key provided in hex format")
+                Arg::with_name("limit")
+                    .long("limit")
+                    .value_name("N")
+                    .help("Prints at most N keys followed by the cursor of the next page on stderr")
                     .required(false)
-                    .takes_value(false),
+                    .takes_value(true),
             )
             .arg(
-                Arg::with_name("from")
-                    .long("from")
-                    .help("key to scan from")
+                Arg::with_name("skip")
+                    .long("skip")
+                    .value_name("N")
+                    .help("Skips the first N keys, ignored when continuing from a cursor")
                     .required(false)
                     .takes_value(true),
             )
             .arg(
-                Arg::with_name("to")
-                    .long("to")
-                    .help("key to scan to")
+                Arg::with_name("cursor")
+                    .long("cursor")
+                    .value_name("CURSOR")
+                    .help("Continues the scan after the page which printed the cursor")
                     .required(false)
                     .takes_value(true),
             )
@@ -142,3 +316,20 @@ impl<'a> Command for Scan<'a> {
         "scan"
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor() {
+        let cursor = Cursor {
+            column_family: "default".to_string(),
+            key: b"\x00key\xff".to_vec(),
+            reverse: true,
+        };
+        assert_eq!(cursor.token().parse::<Cursor>().unwrap(), cursor);
+        assert!("abc".parse::<Cursor>().is_err());
+        assert!(base64::encode("{}").parse::<Cursor>().is_err());
+    }
+}
@@ -0,0 +1,232 @@
+use crate::command::database::{self, OpenMode};
+use crate::command::traits::Command;
+use crate::command::{delete, deleterange, get, put, scan};
+use clap::{App, AppSettings, ArgMatches, SubCommand};
+use rocksdb::{Direction, IteratorMode, DB};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use std::boxed::Box;
+use std::error::Error;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+const PROMPT: &str = "rdbrowser> ";
+const HISTORY_FILE: &str = ".rdbrowser_history";
+const EXIT_COMMANDS: &[&str] = &["exit", "quit"];
+/// Max number of keys offered when completing a key prefix
+const MAX_COMPLETIONS: usize = 100;
+
+/// Names of the commands available in the shell, as registered in `shell_args`
+fn subcommand_names() -> [&'static str; 5] {
+    [
+        put::Put::name(),
+        get::Get::name(),
+        delete::Delete::name(),
+        scan::Scan::name(),
+        deleterange::DeleteRange::name(),
+    ]
+}
+
+/// Builds the parser of a single shell line
+fn shell_args() -> App<'static, 'static> {
+    App::new(env!("CARGO_PKG_NAME"))
+        .setting(AppSettings::NoBinaryName)
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .setting(AppSettings::DisableVersion)
+        .setting(AppSettings::VersionlessSubcommands)
+        .subcommand(put::Put::args())
+        .subcommand(get::Get::args())
+        .subcommand(delete::Delete::args())
+        .subcommand(scan::Scan::args())
+        .subcommand(deleterange::DeleteRange::args())
+}
+
+/// Completes subcommand names in the first word and keys of the DB in the others
+struct ShellHelper {
+    db: Rc<DB>,
+    column_family: String,
+    subcommands: Vec<String>,
+}
+
+impl ShellHelper {
+    fn complete_key(&self, prefix: &str) -> Vec<String> {
+        let cf = match self.db.cf_handle(&self.column_family) {
+            Some(cf) => cf,
+            None => return Vec::new(),
+        };
+        self.db
+            .iterator_cf(
+                cf,
+                IteratorMode::From(prefix.as_bytes(), Direction::Forward),
+            )
+            .take_while(|(key, _)| key.starts_with(prefix.as_bytes()))
+            .filter_map(|(key, _)| String::from_utf8(key.into_vec()).ok())
+            .filter(|key| !key.contains(char::is_whitespace))
+            .take(MAX_COMPLETIONS)
+            .collect()
+    }
+}
+
+impl Completer for ShellHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &line[start..pos];
+        let candidates = if line[..start].trim().is_empty() {
+            self.subcommands
+                .iter()
+                .filter(|name| name.starts_with(word))
+                .cloned()
+                .collect()
+        } else if word.starts_with('-') {
+            Vec::new()
+        } else {
+            self.complete_key(word)
+        };
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+
+impl Validator for ShellHelper {}
+
+impl Helper for ShellHelper {}
+
+#[derive(Debug)]
+pub struct Shell<'a> {
+    db: Rc<DB>,
+    matches: &'a ArgMatches<'a>,
+}
+
+impl<'a> Shell<'a> {
+    pub fn create(matches: &'a ArgMatches<'a>) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
+        let mut opts = Self::build_options(matches);
+        let db = Self::open_db(matches, &mut opts)?;
+        Ok(std::boxed::Box::new(Shell {
+            db: Rc::new(db),
+            matches,
+        }))
+    }
+
+    fn history_path() -> Option<PathBuf> {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE))
+    }
+
+    fn execute(&self, line: &str) -> Result<(), Box<dyn Error>> {
+        let words = shell_words::split(line)?;
+        let line_matches = shell_args().get_matches_from_safe(words)?;
+        let mode = OpenMode::from_matches(self.matches);
+        let db = self.db.clone();
+        let mut command = match line_matches.subcommand() {
+            ("put", Some(sub)) => {
+                database::check_access(mode, put::Put::name(), put::Put::modifies_db())?;
+                put::Put::with_db(db, self.matches, sub)?
+            }
+            ("get", Some(sub)) => get::Get::with_db(db, self.matches, sub)?,
+            ("delete", Some(sub)) => {
+                database::check_access(
+                    mode,
+                    delete::Delete::name(),
+                    delete::Delete::modifies_db(),
+                )?;
+                delete::Delete::with_db(db, self.matches, sub)?
+            }
+            ("scan", Some(sub)) => scan::Scan::with_db(db, self.matches, sub)?,
+            ("deleterange", Some(sub)) => {
+                database::check_access(
+                    mode,
+                    deleterange::DeleteRange::name(),
+                    deleterange::DeleteRange::modifies_db(),
+                )?;
+                deleterange::DeleteRange::with_db(db, self.matches, sub)?
+            }
+            _ => unreachable!(),
+        };
+        command.run()
+    }
+}
+
+impl<'a> Command for Shell<'a> {
+    fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut editor = Editor::<ShellHelper>::new();
+        editor.set_helper(Some(ShellHelper {
+            db: self.db.clone(),
+            column_family: database::column_family(self.matches, Self::name())?.to_string(),
+            subcommands: subcommand_names()
+                .iter()
+                .chain(EXIT_COMMANDS)
+                .map(|name| name.to_string())
+                .collect(),
+        }));
+        let history_path = Self::history_path();
+        if let Some(ref path) = history_path {
+            // There is no history yet on the first run
+            let _ = editor.load_history(path);
+        }
+        loop {
+            match editor.readline(PROMPT) {
+                Ok(line) => {
+                    let line = line.trim();
+                    if line.is_empty() {
+                        continue;
+                    }
+                    editor.add_history_entry(line);
+                    if EXIT_COMMANDS.contains(&line) {
+                        break;
+                    }
+                    if let Err(error) = self.execute(line) {
+                        eprintln!("Failed: {}", error);
+                    }
+                }
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(error) => return Err(Box::new(error)),
+            }
+        }
+        if let Some(ref path) = history_path {
+            editor.save_history(path)?;
+        }
+        Ok(())
+    }
+
+    fn args() -> App<'static, 'static> {
+        SubCommand::with_name(Self::name())
+            .about("Starts interactive shell keeping the DB open between commands")
+    }
+
+    fn name() -> &'static str {
+        "shell"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_shell_line() {
+        let words = shell_words::split("put \"two words\" value").unwrap();
+        let matches = shell_args().get_matches_from_safe(words).unwrap();
+        let (name, sub) = matches.subcommand();
+        assert_eq!(name, "put");
+        assert_eq!(sub.unwrap().value_of("KEY"), Some("two words"));
+        assert!(shell_args()
+            .get_matches_from_safe(vec!["dump", "file"])
+            .is_err());
+    }
+}
@@ -0,0 +1,494 @@
+use crate::command::database;
+use crate::command::range::{self, KeyRange};
+use crate::command::traits::Command;
+use crate::printer::{Encoding, OutputFormat};
+use crate::utils::codec;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use rocksdb::DB;
+use serde_json::{json, Value};
+use simple_error::SimpleError;
+use std::boxed::Box;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::error::Error;
+use std::io::{self, Write};
+
+/// Width of the longest bar of the text histograms
+const BAR_WIDTH: u64 = 40;
+
+/// Counts of sizes in power of two buckets: 0, 1, 2-3, 4-7, ...
+#[derive(Debug, Default)]
+struct Histogram {
+    buckets: Vec<u64>,
+    count: u64,
+    sum: u64,
+    min: Option<usize>,
+    max: usize,
+}
+
+impl Histogram {
+    fn add(&mut self, size: usize) {
+        let bucket = (usize::BITS - size.leading_zeros()) as usize;
+        if self.buckets.len() <= bucket {
+            self.buckets.resize(bucket + 1, 0);
+        }
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.sum += size as u64;
+        self.min = Some(self.min.map_or(size, |min| min.min(size)));
+        self.max = self.max.max(size);
+    }
+
+    fn label(bucket: usize) -> String {
+        match bucket {
+            0 => String::from("0"),
+            1 => String::from("1"),
+            _ => format!("{}-{}", 1u64 << (bucket - 1), (1u64 << bucket) - 1),
+        }
+    }
+
+    fn mean(&self) -> f64 {
+        match self.count {
+            0 => 0.0,
+            count => self.sum as f64 / count as f64,
+        }
+    }
+
+    /// Non empty buckets along with their labels
+    fn rows(&self) -> impl Iterator<Item = (String, u64)> + '_ {
+        self.buckets
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(bucket, count)| (Histogram::label(bucket), *count))
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "min": self.min.unwrap_or(0),
+            "max": self.max,
+            "mean": self.mean(),
+            "buckets": self
+                .rows()
+                .map(|(label, count)| json!({"bytes": label, "count": count}))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    fn write(&self, out: &mut dyn Write, title: &str) -> io::Result<()> {
+        writeln!(
+            out,
+            "{}: min {}, max {}, mean {:.1}",
+            title,
+            self.min.unwrap_or(0),
+            self.max,
+            self.mean()
+        )?;
+        let highest = self.buckets.iter().copied().max().unwrap_or(0).max(1);
+        for (label, count) in self.rows() {
+            // Every non empty bucket gets at least a tick
+            let bar = (count * BAR_WIDTH).div_ceil(highest) as usize;
+            writeln!(out, "  {:>13} {:>10} {}", label, count, "#".repeat(bar))?;
+        }
+        Ok(())
+    }
+}
+
+/// The count followed by the unit, in plural unless there is one
+fn quantity(count: u64, unit: &str) -> String {
+    match count {
+        1 => format!("1 {}", unit),
+        count => format!("{} {}s", count, unit),
+    }
+}
+
+/// The first `depth` bytes of the key, or its first `depth` segments ending with the delimiter
+fn key_prefix(key: &[u8], depth: usize, delimiter: Option<u8>) -> &[u8] {
+    let length = match delimiter {
+        None => depth.min(key.len()),
+        Some(_) if depth == 0 => 0,
+        Some(delimiter) => key
+            .iter()
+            .enumerate()
+            .filter(|(_, byte)| **byte == delimiter)
+            .nth(depth - 1)
+            .map_or(key.len(), |(position, _)| position + 1),
+    };
+    &key[..length]
+}
+
+/// Numbers of keys and bytes sharing a prefix
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct PrefixTotals {
+    keys: u64,
+    bytes: u64,
+}
+
+/// Shape of the keys and values of a column family
+#[derive(Debug)]
+struct Report {
+    column_family: String,
+    examined: u64,
+    key_length: Histogram,
+    value_size: Histogram,
+    /// Totals by prefix for each depth
+    prefixes: Vec<HashMap<Vec<u8>, PrefixTotals>>,
+    /// The largest values as a min heap of their sizes and keys
+    largest: BinaryHeap<Reverse<(usize, Vec<u8>)>>,
+}
+
+impl Report {
+    fn new(column_family: &str, depths: usize) -> Report {
+        Report {
+            column_family: column_family.to_string(),
+            examined: 0,
+            key_length: Histogram::default(),
+            value_size: Histogram::default(),
+            prefixes: vec![HashMap::new(); depths],
+            largest: BinaryHeap::new(),
+        }
+    }
+
+    fn total_bytes(&self) -> u64 {
+        self.key_length.sum + self.value_size.sum
+    }
+
+    /// The most common prefixes of each depth, by number of keys
+    fn top_prefixes(&self, top: usize) -> Vec<Vec<(&[u8], PrefixTotals)>> {
+        self.prefixes
+            .iter()
+            .map(|prefixes| {
+                let mut prefixes: Vec<_> = prefixes
+                    .iter()
+                    .map(|(prefix, totals)| (prefix.as_slice(), *totals))
+                    .collect();
+                prefixes.sort_unstable_by(|(a, a_totals), (b, b_totals)| {
+                    b_totals.keys.cmp(&a_totals.keys).then(a.cmp(b))
+                });
+                prefixes.truncate(top);
+                prefixes
+            })
+            .collect()
+    }
+
+    /// The largest values from the largest one down
+    fn largest_values(&self) -> Vec<(usize, &[u8])> {
+        let mut largest: Vec<_> = self
+            .largest
+            .iter()
+            .map(|Reverse((size, key))| (*size, key.as_slice()))
+            .collect();
+        largest.sort_unstable_by(|(a, a_key), (b, b_key)| b.cmp(a).then(a_key.cmp(b_key)));
+        largest
+    }
+
+    fn share(&self, bytes: u64) -> f64 {
+        match self.total_bytes() {
+            0 => 0.0,
+            total => bytes as f64 * 100.0 / total as f64,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Stats {
+    db: DB,
+    column_families: Vec<String>,
+    range: KeyRange,
+    prefix_length: Option<usize>,
+    depths: Vec<usize>,
+    delimiter: Option<u8>,
+    top: usize,
+    /// Every how many keys one is sampled
+    sample: usize,
+    encoding: Encoding,
+    output_format: OutputFormat,
+}
+
+impl Stats {
+    pub fn create<'a>(
+        matches: &'a ArgMatches<'a>,
+    ) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
+        let mut opts = Self::build_options(matches);
+        let subcommand_matches = matches.subcommand_matches(Self::name()).unwrap();
+        range::set_prefix_extractor(&mut opts, subcommand_matches)?;
+        let db = Self::open_db(matches, &mut opts)?;
+        let column_families = database::target_column_families(matches, subcommand_matches);
+        let encoding = Encoding::from_matches(matches, subcommand_matches)?;
+        let sample = subcommand_matches
+            .value_of("sample")
+            .unwrap()
+            .parse::<usize>()?;
+        if sample == 0 {
+            return Err(Box::new(SimpleError::new(
+                "Sampling interval must be positive",
+            )));
+        }
+        let delimiter = match subcommand_matches.value_of("prefix_delimiter") {
+            None => None,
+            Some(delimiter) if delimiter.len() == 1 => Some(delimiter.as_bytes()[0]),
+            Some(delimiter) => {
+                return Err(Box::new(SimpleError::new(format!(
+                    "Prefix delimiter must be a single byte, got {}",
+                    delimiter
+                ))))
+            }
+        };
+        Ok(std::boxed::Box::new(Stats {
+            db,
+            column_families,
+            range: KeyRange::from_matches(subcommand_matches, |key| {
+                encoding.decode_key(None, key)
+            })?,
+            prefix_length: range::prefix_length(subcommand_matches)?,
+            depths: subcommand_matches
+                .value_of("prefix_depth")
+                .unwrap()
+                .split(',')
+                .map(|depth| depth.trim().parse::<usize>())
+                .collect::<Result<_, _>>()?,
+            delimiter,
+            top: subcommand_matches.value_of("top").unwrap().parse()?,
+            sample,
+            encoding,
+            output_format: matches.value_of("output_format").unwrap().parse()?,
+        }))
+    }
+
+    fn report(&self, name: &str) -> Result<Report, Box<dyn Error>> {
+        let cf = database::cf_handle(&self.db, name)?;
+        let iter = self.db.iterator_cf_opt(
+            cf,
+            self.range.read_options(self.prefix_length, false)?,
+            self.range.mode(),
+        );
+        let mut report = Report::new(name, self.depths.len());
+        for (key, value) in iter.take_while(|(key, _)| self.range.contains(key)) {
+            let sampled = report.examined.is_multiple_of(self.sample as u64);
+            report.examined += 1;
+            if !sampled {
+                continue;
+            }
+            report.key_length.add(key.len());
+            report.value_size.add(value.len());
+            let bytes = (key.len() + value.len()) as u64;
+            for (prefixes, depth) in report.prefixes.iter_mut().zip(&self.depths) {
+                let prefix = key_prefix(&key, *depth, self.delimiter);
+                // Looked up before inserting, so known prefixes are not copied
+                match prefixes.get_mut(prefix) {
+                    Some(totals) => {
+                        totals.keys += 1;
+                        totals.bytes += bytes;
+                    }
+                    None => {
+                        prefixes.insert(prefix.to_vec(), PrefixTotals { keys: 1, bytes });
+                    }
+                }
+            }
+            if self.top > 0 {
+                let smallest = report.largest.peek().map(|Reverse((size, _))| *size);
+                if report.largest.len() < self.top {
+                    report.largest.push(Reverse((value.len(), key.to_vec())));
+                } else if smallest.is_some_and(|size| value.len() > size) {
+                    report.largest.pop();
+                    report.largest.push(Reverse((value.len(), key.to_vec())));
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    fn to_json(&self, report: &Report) -> Value {
+        let key = |key: &[u8]| self.encoding.key(Some(&report.column_family), key);
+        json!({
+            "column_family": report.column_family,
+            "examined": report.examined,
+            "sample": self.sample,
+            "keys": report.key_length.count,
+            "key_bytes": report.key_length.sum,
+            "value_bytes": report.value_size.sum,
+            "key_length": report.key_length.to_json(),
+            "value_size": report.value_size.to_json(),
+            "prefixes": self
+                .depths
+                .iter()
+                .zip(report.top_prefixes(self.top))
+                .map(|(depth, prefixes)| json!({
+                    "depth": depth,
+                    "top": prefixes
+                        .iter()
+                        .map(|(prefix, totals)| json!({
+                            "prefix": key(prefix),
+                            "keys": totals.keys,
+                            "bytes": totals.bytes,
+                            "share": report.share(totals.bytes),
+                        }))
+                        .collect::<Vec<_>>(),
+                }))
+                .collect::<Vec<_>>(),
+            "largest_values": report
+                .largest_values()
+                .iter()
+                .map(|(size, k)| json!({"key": key(k), "size": size}))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    fn write_text(&self, out: &mut dyn Write, report: &Report) -> io::Result<()> {
+        let key = |key: &[u8]| self.encoding.key(Some(&report.column_family), key);
+        writeln!(out, "Column family: {}", report.column_family)?;
+        if self.sample > 1 {
+            writeln!(
+                out,
+                "Keys: {} examined, {} sampled every {} keys",
+                report.examined, report.key_length.count, self.sample
+            )?;
+        } else {
+            writeln!(out, "Keys: {}", report.examined)?;
+        }
+        writeln!(
+            out,
+            "Bytes: {} of keys, {} of values",
+            report.key_length.sum, report.value_size.sum
+        )?;
+        report.key_length.write(out, "Key length")?;
+        report.value_size.write(out, "Value size")?;
+        let unit = if self.delimiter.is_some() {
+            "segment"
+        } else {
+            "byte"
+        };
+        for (depth, prefixes) in self.depths.iter().zip(report.top_prefixes(self.top)) {
+            writeln!(out, "Top prefixes of {}:", quantity(*depth as u64, unit))?;
+            for (prefix, totals) in prefixes {
+                writeln!(
+                    out,
+                    "  {} : {}, {}, {:.2}%",
+                    key(prefix),
+                    quantity(totals.keys, "key"),
+                    quantity(totals.bytes, "byte"),
+                    report.share(totals.bytes)
+                )?;
+            }
+        }
+        if self.top > 0 {
+            writeln!(out, "Largest values:")?;
+            for (size, k) in report.largest_values() {
+                writeln!(out, "  {} : {}", key(k), quantity(size as u64, "byte"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Command for Stats {
+    fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        let stdout = io::stdout();
+        let mut out = stdout.lock();
+        let mut reports = Vec::new();
+        for (i, name) in self.column_families.iter().enumerate() {
+            let report = self.report(name)?;
+            match self.output_format {
+                OutputFormat::Json => reports.push(self.to_json(&report)),
+                OutputFormat::Jsonl => writeln!(out, "{}", self.to_json(&report))?,
+                _ => {
+                    if i > 0 {
+                        writeln!(out)?;
+                    }
+                    self.write_text(&mut out, &report)?;
+                }
+            }
+        }
+        if self.output_format == OutputFormat::Json {
+            serde_json::to_writer_pretty(&mut out, &reports)?;
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    fn args() -> App<'static, 'static> {
+        SubCommand::with_name(Self::name())
+            .about("Prints key length and value size histograms, top key prefixes and largest values, as JSON with --output-format json or jsonl")
+            .args(&codec::key_args())
+            .arg(database::all_column_families_arg())
+            .args(&range::args())
+            .arg(range::prefix_length_arg())
+            .arg(
+                Arg::with_name("prefix_depth")
+                    .long("prefix-depth")
+                    .value_name("DEPTHS")
+                    .help("Comma separated lengths of the key prefixes counted, in bytes or in segments with --prefix-delimiter")
+                    .required(false)
+                    .takes_value(true)
+                    .default_value("1"),
+            )
+            .arg(
+                Arg::with_name("prefix_delimiter")
+                    .long("prefix-delimiter")
+                    .value_name("CHAR")
+                    .help("Byte ending the segments of the keys, e.g. : for user:1:name")
+                    .required(false)
+                    .takes_value(true),
+            )
+            .arg(
+                Arg::with_name("top")
+                    .long("top")
+                    .value_name("N")
+                    .help("Number of prefixes of each depth and of largest values shown")
+                    .required(false)
+                    .takes_value(true)
+                    .default_value("10"),
+            )
+            .arg(
+                Arg::with_name("sample")
+                    .long("sample")
+                    .value_name("N")
+                    .help("Samples every Nth key for huge DBs")
+                    .required(false)
+                    .takes_value(true)
+                    .default_value("1"),
+            )
+    }
+
+    fn name() -> &'static str {
+        "stats"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram() {
+        let mut histogram = Histogram::default();
+        for size in [0, 1, 2, 3, 4, 7, 8, 1000] {
+            histogram.add(size);
+        }
+        let rows: Vec<_> = histogram.rows().collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("0".to_string(), 1),
+                ("1".to_string(), 1),
+                ("2-3".to_string(), 2),
+                ("4-7".to_string(), 2),
+                ("8-15".to_string(), 1),
+                ("512-1023".to_string(), 1),
+            ]
+        );
+        assert_eq!(histogram.min, Some(0));
+        assert_eq!(histogram.max, 1000);
+        assert_eq!(histogram.mean(), 128.125);
+    }
+
+    #[test]
+    fn test_key_prefix() {
+        assert_eq!(key_prefix(b"user:1:name", 2, None), b"us");
+        assert_eq!(key_prefix(b"u", 2, None), b"u");
+        assert_eq!(key_prefix(b"user:1:name", 1, Some(b':')), b"user:");
+        assert_eq!(key_prefix(b"user:1:name", 2, Some(b':')), b"user:1:");
+        assert_eq!(key_prefix(b"user:1:name", 3, Some(b':')), b"user:1:name");
+        assert_eq!(key_prefix(b"user", 0, Some(b':')), b"");
+    }
+}
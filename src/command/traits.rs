@@ -1,5 +1,6 @@
+use super::database;
 use clap::{App, ArgMatches};
-use rocksdb::Options;
+use rocksdb::{Options, DB};
 use std::boxed::Box;
 
 pub trait Command {
@@ -9,9 +10,28 @@ pub trait Command {
     {
         let mut opts = Options::default();
         opts.create_if_missing(matches.is_present("create_if_missing"));
+        opts.create_missing_column_families(matches.is_present("create_if_missing"));
         opts
     }
 
+    fn open_db<'a>(
+        matches: &'a ArgMatches<'a>,
+        opts: &mut Options,
+    ) -> Result<DB, Box<dyn std::error::Error>>
+    where
+        Self: Sized,
+    {
+        database::open(matches, opts, Self::name(), Self::modifies_db())
+    }
+
+    /// Whether the command writes to the DB, such commands are refused in read-only modes
+    fn modifies_db() -> bool
+    where
+        Self: Sized,
+    {
+        false
+    }
+
     fn args() -> App<'static, 'static>
     where
         Self: Sized;
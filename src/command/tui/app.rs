@@ -0,0 +1,466 @@
+use crate::command::database;
+use crate::utils;
+use crate::utils::codec::Codec;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use rocksdb::{Direction, IteratorMode, DB};
+use simple_error::SimpleError;
+use std::boxed::Box;
+use std::error::Error;
+use std::rc::Rc;
+use tui::widgets::ListState;
+
+/// How the value of the selected key is shown
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueView {
+    Utf8,
+    Hex,
+    /// Pretty printed JSON, other values with non-printable bytes escaped
+    Decoded,
+}
+
+impl ValueView {
+    pub fn next(self) -> ValueView {
+        match self {
+            ValueView::Utf8 => ValueView::Hex,
+            ValueView::Hex => ValueView::Decoded,
+            ValueView::Decoded => ValueView::Utf8,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ValueView::Utf8 => "utf-8",
+            ValueView::Hex => "hex",
+            ValueView::Decoded => "decoded",
+        }
+    }
+
+    pub fn render(self, value: &[u8]) -> String {
+        match self {
+            ValueView::Utf8 => String::from_utf8_lossy(value).into_owned(),
+            ValueView::Hex => utils::hex::encode(value),
+            ValueView::Decoded => match serde_json::from_slice::<serde_json::Value>(value) {
+                Ok(json) => serde_json::to_string_pretty(&json).unwrap(),
+                Err(_) => value
+                    .iter()
+                    .flat_map(|b| std::ascii::escape_default(*b))
+                    .map(char::from)
+                    .collect(),
+            },
+        }
+    }
+
+    /// The view the value is edited in, values which are not UTF-8 are edited as hex so that
+    /// saving them does not replace their invalid bytes
+    pub fn editable(self, value: &[u8]) -> ValueView {
+        if self == ValueView::Hex || std::str::from_utf8(value).is_ok() {
+            self
+        } else {
+            ValueView::Hex
+        }
+    }
+
+    /// Parses a value typed in the view, decoded values are edited as UTF-8
+    pub fn parse(self, text: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        match self {
+            ValueView::Hex => Ok(utils::hex::decode(text)?),
+            ValueView::Utf8 | ValueView::Decoded => Ok(text.as_bytes().to_vec()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputKind {
+    Seek,
+    Filter,
+    Edit,
+}
+
+impl InputKind {
+    pub fn title(self) -> &'static str {
+        match self {
+            InputKind::Seek => "Seek to key",
+            InputKind::Filter => "Key prefix filter",
+            InputKind::Edit => "New value",
+        }
+    }
+}
+
+/// A line being typed at the bottom of the screen
+#[derive(Debug)]
+pub struct Input {
+    pub kind: InputKind,
+    pub text: String,
+}
+
+/// A modification waiting for the user to confirm it
+#[derive(Debug)]
+pub enum Confirm {
+    Save { key: Vec<u8>, value: Vec<u8> },
+    Delete { key: Vec<u8> },
+}
+
+pub struct App {
+    db: Rc<DB>,
+    /// Why the DB cannot be modified, if it cannot
+    read_only: Option<String>,
+    pub column_families: Vec<String>,
+    pub column_family: usize,
+    page_size: usize,
+    key_codec: Codec,
+    pub prefix: Vec<u8>,
+    /// First keys of the pages before the current one
+    previous_pages: Vec<Vec<u8>>,
+    pub page: usize,
+    pub keys: Vec<Vec<u8>>,
+    next_page: Option<Vec<u8>>,
+    pub list_state: ListState,
+    pub value: Option<Vec<u8>>,
+    pub view: ValueView,
+    pub value_scroll: u16,
+    pub input: Option<Input>,
+    pub confirm: Option<Confirm>,
+    pub status: String,
+    pub should_quit: bool,
+}
+
+impl App {
+    pub fn new(
+        db: Rc<DB>,
+        read_only: Option<String>,
+        column_families: Vec<String>,
+        column_family: usize,
+        page_size: usize,
+        key_codec: Codec,
+    ) -> Result<App, Box<dyn Error>> {
+        let mut app = App {
+            db,
+            read_only,
+            column_families,
+            column_family,
+            page_size,
+            key_codec,
+            prefix: Vec::new(),
+            previous_pages: Vec::new(),
+            page: 0,
+            keys: Vec::new(),
+            next_page: None,
+            list_state: ListState::default(),
+            value: None,
+            view: ValueView::Utf8,
+            value_scroll: 0,
+            input: None,
+            confirm: None,
+            status: String::new(),
+            should_quit: false,
+        };
+        app.reset(None)?;
+        Ok(app)
+    }
+
+    pub fn format_key(&self, key: &[u8]) -> String {
+        self.key_codec.encode(key)
+    }
+
+    fn parse_key(&self, text: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.key_codec.decode(text)
+    }
+
+    pub fn selected_key(&self) -> Option<&[u8]> {
+        self.list_state
+            .selected()
+            .and_then(|i| self.keys.get(i))
+            .map(Vec::as_slice)
+    }
+
+    /// Starts paging from the given key, or from the beginning of the prefix
+    fn reset(&mut self, from: Option<Vec<u8>>) -> Result<(), Box<dyn Error>> {
+        self.previous_pages.clear();
+        self.page = 0;
+        let from = match from {
+            Some(key) if key > self.prefix => key,
+            _ => self.prefix.clone(),
+        };
+        self.load_page(from)?;
+        self.select(Some(0))
+    }
+
+    /// Loads a page of keys starting from the given one
+    fn load_page(&mut self, from: Vec<u8>) -> Result<(), Box<dyn Error>> {
+        let cf = database::cf_handle(&self.db, &self.column_families[self.column_family])?;
+        let mut keys: Vec<Vec<u8>> = self
+            .db
+            .iterator_cf(cf, IteratorMode::From(&from, Direction::Forward))
+            .map(|(key, _)| key.into_vec())
+            .take_while(|key| key.starts_with(&self.prefix))
+            .take(self.page_size + 1)
+            .collect();
+        self.next_page = if keys.len() > self.page_size {
+            keys.pop()
+        } else {
+            None
+        };
+        self.keys = keys;
+        Ok(())
+    }
+
+    fn select(&mut self, index: Option<usize>) -> Result<(), Box<dyn Error>> {
+        let index = if self.keys.is_empty() {
+            None
+        } else {
+            index.map(|i| i.min(self.keys.len() - 1))
+        };
+        self.list_state.select(index);
+        self.value_scroll = 0;
+        self.value = match self.selected_key() {
+            Some(key) => {
+                let cf = database::cf_handle(&self.db, &self.column_families[self.column_family])?;
+                self.db.get_cf(cf, key)?
+            }
+            None => None,
+        };
+        Ok(())
+    }
+
+    fn next_page(&mut self) -> Result<bool, Box<dyn Error>> {
+        match self.next_page.take() {
+            Some(from) => {
+                self.previous_pages
+                    .push(self.keys.first().cloned().unwrap_or_default());
+                self.page += 1;
+                self.load_page(from)?;
+                self.select(Some(0))?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn previous_page(&mut self) -> Result<bool, Box<dyn Error>> {
+        match self.previous_pages.pop() {
+            Some(from) => {
+                self.page -= 1;
+                self.load_page(from)?;
+                self.select(Some(self.keys.len().saturating_sub(1)))?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Reloads the current page after the DB was modified
+    fn reload(&mut self) -> Result<(), Box<dyn Error>> {
+        let selected = self.list_state.selected();
+        let from = match self.keys.first() {
+            Some(key) => key.clone(),
+            None => self.prefix.clone(),
+        };
+        self.load_page(from)?;
+        self.select(selected.or(Some(0)))
+    }
+
+    fn switch_column_family(&mut self, forward: bool) -> Result<(), Box<dyn Error>> {
+        let count = self.column_families.len();
+        self.column_family = if forward {
+            (self.column_family + 1) % count
+        } else {
+            (self.column_family + count - 1) % count
+        };
+        self.reset(None)?;
+        self.status = format!(
+            "Column family: {}",
+            self.column_families[self.column_family]
+        );
+        Ok(())
+    }
+
+    fn start_input(&mut self, kind: InputKind) {
+        let text = match kind {
+            InputKind::Seek => String::new(),
+            InputKind::Filter => self.format_key(&self.prefix),
+            InputKind::Edit => match self.value {
+                Some(ref value) => {
+                    let view = self.view.editable(value);
+                    if view != self.view {
+                        self.view = view;
+                        self.status = String::from("Value is not UTF-8, editing it as hex");
+                    }
+                    match view {
+                        ValueView::Hex => utils::hex::encode(value),
+                        ValueView::Utf8 | ValueView::Decoded => {
+                            String::from_utf8_lossy(value).into_owned()
+                        }
+                    }
+                }
+                None => String::new(),
+            },
+        };
+        self.input = Some(Input { kind, text });
+    }
+
+    fn submit_input(&mut self, input: Input) -> Result<(), Box<dyn Error>> {
+        match input.kind {
+            InputKind::Seek => {
+                let key = self.parse_key(&input.text)?;
+                self.reset(Some(key))?;
+            }
+            InputKind::Filter => {
+                self.prefix = self.parse_key(&input.text)?;
+                self.reset(None)?;
+            }
+            InputKind::Edit => {
+                if let Some(key) = self.selected_key() {
+                    let key = key.to_vec();
+                    let value = self.view.parse(&input.text)?;
+                    self.status = format!("Save new value of {}? [y/N]", self.format_key(&key));
+                    self.confirm = Some(Confirm::Save { key, value });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn apply(&mut self, confirm: Confirm) -> Result<(), Box<dyn Error>> {
+        let cf = database::cf_handle(&self.db, &self.column_families[self.column_family])?;
+        match confirm {
+            Confirm::Save { key, value } => {
+                self.db.put_cf(cf, &key, value)?;
+                self.status = format!("Saved {}", self.format_key(&key));
+            }
+            Confirm::Delete { key } => {
+                self.db.delete_cf(cf, &key)?;
+                self.status = format!("Deleted {}", self.format_key(&key));
+            }
+        }
+        self.reload()
+    }
+
+    /// Refuses modifications of a DB opened in a read-only mode
+    fn check_writable(&self) -> Result<(), Box<dyn Error>> {
+        match self.read_only {
+            Some(ref reason) => Err(Box::new(SimpleError::new(reason.as_str()))),
+            None => Ok(()),
+        }
+    }
+
+    /// Handles a key press, errors are shown in the status line
+    pub fn on_key(&mut self, key: KeyEvent) {
+        if let Err(error) = self.handle_key(key) {
+            self.status = format!("Failed: {}", error);
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Result<(), Box<dyn Error>> {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.should_quit = true;
+            return Ok(());
+        }
+        if let Some(confirm) = self.confirm.take() {
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => self.apply(confirm)?,
+                _ => self.status = "Cancelled".to_string(),
+            }
+            return Ok(());
+        }
+        if let Some(mut input) = self.input.take() {
+            match key.code {
+                KeyCode::Enter => self.submit_input(input)?,
+                KeyCode::Esc => {}
+                KeyCode::Backspace => {
+                    input.text.pop();
+                    self.input = Some(input);
+                }
+                KeyCode::Char(c) => {
+                    input.text.push(c);
+                    self.input = Some(input);
+                }
+                _ => self.input = Some(input),
+            }
+            return Ok(());
+        }
+        self.status.clear();
+        let selected = self.list_state.selected().unwrap_or(0);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.should_quit = true,
+            KeyCode::Down | KeyCode::Char('j') => {
+                if selected + 1 < self.keys.len() {
+                    self.select(Some(selected + 1))?;
+                } else {
+                    self.next_page()?;
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                if selected > 0 {
+                    self.select(Some(selected - 1))?;
+                } else {
+                    self.previous_page()?;
+                }
+            }
+            KeyCode::PageDown | KeyCode::Char('n') => {
+                // Stay on the last page, moving to its last key
+                let moved = self.next_page()?;
+                if !moved {
+                    self.select(Some(self.keys.len().saturating_sub(1)))?;
+                }
+            }
+            KeyCode::PageUp | KeyCode::Char('p') => {
+                let moved = self.previous_page()?;
+                if !moved {
+                    self.select(Some(0))?;
+                }
+            }
+            KeyCode::Home | KeyCode::Char('g') => self.reset(None)?,
+            KeyCode::Char('J') => self.value_scroll = self.value_scroll.saturating_add(1),
+            KeyCode::Char('K') => self.value_scroll = self.value_scroll.saturating_sub(1),
+            KeyCode::Char('v') => self.view = self.view.next(),
+            KeyCode::Char('c') => self.switch_column_family(true)?,
+            KeyCode::Char('C') => self.switch_column_family(false)?,
+            KeyCode::Char('r') => self.reload()?,
+            KeyCode::Char('/') => self.start_input(InputKind::Seek),
+            KeyCode::Char('f') => self.start_input(InputKind::Filter),
+            KeyCode::Char('e') if self.selected_key().is_some() => {
+                self.check_writable()?;
+                self.start_input(InputKind::Edit);
+            }
+            KeyCode::Char('d') => {
+                if let Some(key) = self.selected_key().map(<[u8]>::to_vec) {
+                    self.check_writable()?;
+                    self.status = format!("Delete {}? [y/N]", self.format_key(&key));
+                    self.confirm = Some(Confirm::Delete { key });
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn value_views() {
+        assert_eq!(ValueView::Utf8.render(b"value"), "value");
+        assert_eq!(ValueView::Hex.render(b"value"), "0x76616c7565");
+        assert_eq!(ValueView::Decoded.render(br#"{"a":1}"#), "{\n  \"a\": 1\n}");
+        assert_eq!(ValueView::Decoded.render(b"a\x00\xff"), "a\\x00\\xff");
+        assert_eq!(ValueView::Utf8.next().next().next(), ValueView::Utf8);
+    }
+
+    #[test]
+    fn parse_edited_value() {
+        assert_eq!(ValueView::Hex.parse("76616c7565").unwrap(), b"value");
+        assert_eq!(ValueView::Decoded.parse("value").unwrap(), b"value");
+        assert!(ValueView::Hex.parse("xyz").is_err());
+    }
+
+    #[test]
+    fn binary_values_are_edited_as_hex() {
+        assert_eq!(ValueView::Utf8.editable(b"value"), ValueView::Utf8);
+        assert_eq!(ValueView::Decoded.editable(b"{}"), ValueView::Decoded);
+        assert_eq!(ValueView::Utf8.editable(b"a\xff"), ValueView::Hex);
+        assert_eq!(ValueView::Decoded.editable(b"a\xff"), ValueView::Hex);
+        assert_eq!(ValueView::Hex.editable(b"a\xff"), ValueView::Hex);
+    }
+}
@@ -0,0 +1,135 @@
+mod app;
+mod ui;
+
+use crate::command::database::{self, OpenMode};
+use crate::command::traits::Command;
+use crate::utils::codec::{self, Codec};
+use app::App;
+use clap::{App as ClapApp, Arg, ArgMatches, SubCommand};
+use crossterm::cursor;
+use crossterm::event::{self, Event, KeyEventKind};
+use crossterm::execute;
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use rocksdb::DB;
+use simple_error::SimpleError;
+use std::boxed::Box;
+use std::error::Error;
+use std::io;
+use std::rc::Rc;
+use tui::backend::{Backend, CrosstermBackend};
+use tui::Terminal;
+
+#[derive(Debug)]
+pub struct Tui<'a> {
+    db: Rc<DB>,
+    matches: &'a ArgMatches<'a>,
+    column_families: Vec<String>,
+    page_size: usize,
+    key_codec: Codec,
+}
+
+impl<'a> Tui<'a> {
+    pub fn create(matches: &'a ArgMatches<'a>) -> Result<Box<dyn Command + 'a>, Box<dyn Error>> {
+        let mut opts = Self::build_options(matches);
+        let db = Self::open_db(matches, &mut opts)?;
+        let subcommand_matches = matches.subcommand_matches(Self::name()).unwrap();
+        // The column family switcher offers all column families of the DB
+        let mut column_families =
+            database::list_column_families(&opts, matches.value_of("db").unwrap());
+        if column_families.is_empty() {
+            column_families.push(database::column_family(matches, Self::name())?.to_string());
+        }
+        let page_size = subcommand_matches
+            .value_of("page_size")
+            .unwrap()
+            .parse::<usize>()?;
+        if page_size == 0 {
+            return Err(Box::new(SimpleError::new("Page size must be positive")));
+        }
+        Ok(std::boxed::Box::new(Tui {
+            db: Rc::new(db),
+            matches,
+            column_families,
+            page_size,
+            key_codec: codec::key_codec(subcommand_matches)?.unwrap_or_default(),
+        }))
+    }
+
+    fn event_loop<B: Backend>(
+        terminal: &mut Terminal<B>,
+        app: &mut App,
+    ) -> Result<(), Box<dyn Error>> {
+        while !app.should_quit {
+            terminal.draw(|f| ui::draw(f, app))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    app.on_key(key);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Command for Tui<'a> {
+    fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        let column_family = database::column_family(self.matches, Self::name())?;
+        let selected = self
+            .column_families
+            .iter()
+            .position(|name| name == column_family)
+            .unwrap_or(0);
+        // The DB is only modified after confirmation, so edits are checked when requested
+        let read_only =
+            database::check_access(OpenMode::from_matches(self.matches), "Editing", true)
+                .err()
+                .map(|error| error.to_string());
+        let mut app = App::new(
+            self.db.clone(),
+            read_only,
+            self.column_families.clone(),
+            selected,
+            self.page_size,
+            self.key_codec.clone(),
+        )?;
+
+        // A panic would otherwise leave the shell in raw mode on the alternate screen
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let _ = disable_raw_mode();
+            let _ = execute!(io::stdout(), LeaveAlternateScreen, cursor::Show);
+            default_hook(info);
+        }));
+        enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        execute!(stdout, EnterAlternateScreen)?;
+        let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+        let result = Self::event_loop(&mut terminal, &mut app);
+        // Restore the terminal even if the event loop failed
+        disable_raw_mode()?;
+        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+        terminal.show_cursor()?;
+        let _ = std::panic::take_hook();
+        result
+    }
+
+    fn args() -> ClapApp<'static, 'static> {
+        SubCommand::with_name(Self::name())
+            .about("Browses the DB in a full-screen terminal UI")
+            .args(&codec::key_args())
+            .arg(
+                Arg::with_name("page_size")
+                    .long("page-size")
+                    .help("Number of keys loaded at once")
+                    .required(false)
+                    .takes_value(true)
+                    .default_value("100"),
+            )
+    }
+
+    fn name() -> &'static str {
+        "tui"
+    }
+}
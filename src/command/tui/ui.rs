@@ -0,0 +1,88 @@
+use super::app::App;
+use tui::backend::Backend;
+use tui::layout::{Constraint, Direction, Layout, Rect};
+use tui::style::{Modifier, Style};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, List, ListItem, Paragraph, Wrap};
+use tui::Frame;
+
+const HELP: &str = "q quit  j/k move  n/p page  / seek  f filter  c cf  v view  J/K scroll value  e edit  d delete  r reload";
+
+pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(3)].as_ref())
+        .split(f.size());
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+        .split(rows[0]);
+    draw_keys(f, app, panes[0]);
+    draw_value(f, app, panes[1]);
+    draw_bottom(f, app, rows[1]);
+}
+
+fn draw_keys<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let mut title = format!(
+        " {} | page {} ",
+        app.column_families[app.column_family],
+        app.page + 1
+    );
+    if !app.prefix.is_empty() {
+        title.push_str(&format!("| prefix {} ", app.format_key(&app.prefix)));
+    }
+    let items: Vec<ListItem> = app
+        .keys
+        .iter()
+        .map(|key| ListItem::new(app.format_key(key)))
+        .collect();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
+    f.render_stateful_widget(list, area, &mut app.list_state);
+}
+
+fn draw_value<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let text = match app.value {
+        Some(ref value) => app.view.render(value),
+        None => String::new(),
+    };
+    let paragraph = Paragraph::new(text)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!(" value ({}) ", app.view.name())),
+        )
+        .wrap(Wrap { trim: false })
+        .scroll((app.value_scroll, 0));
+    f.render_widget(paragraph, area);
+}
+
+fn draw_bottom<B: Backend>(f: &mut Frame<B>, app: &App, area: Rect) {
+    let block = Block::default().borders(Borders::ALL);
+    match app.input {
+        Some(ref input) => {
+            let paragraph = Paragraph::new(input.text.as_str())
+                .block(block.title(format!(" {} ", input.kind.title())));
+            f.render_widget(paragraph, area);
+            // Keep the cursor right after the typed text
+            let width = input.text.chars().count() as u16;
+            f.set_cursor(
+                (area.x + 1 + width).min(area.x + area.width.saturating_sub(2)),
+                area.y + 1,
+            );
+        }
+        None => {
+            let line = if app.status.is_empty() {
+                Spans::from(HELP)
+            } else {
+                Spans::from(Span::styled(
+                    app.status.as_str(),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ))
+            };
+            f.render_widget(Paragraph::new(line).block(block), area);
+        }
+    }
+}
@@ -0,0 +1,465 @@
+//! Lossless binary dump format
+//!
+//! All integers are little endian, byte strings are prefixed with their `u32` length:
+//!
+//! ```text
+//! magic "RDBDUMP\0" | version u32 | source path | sequence number u64
+//! column family count u32 | column family name (repeated)
+//! for every column family:
+//!     SECTION tag | column family name
+//!     RECORD tag | key | value        (repeated)
+//!     SECTION_END tag | key count u64
+//! TRAILER tag | total key count u64 | crc32 of all the preceding bytes u32
+//! ```
+//!
+//! The header lists the dumped column families, so the content of a dump is known without
+//! scanning it. Key counts are not known until the records are streamed, so they follow the
+//! records of every section and of the whole dump instead.
+use crate::printer::Printer;
+use clap::Arg;
+use simple_error::SimpleError;
+use std::boxed::Box;
+use std::error::Error;
+use std::io::{ErrorKind, Read, Write};
+use std::str::FromStr;
+
+pub const MAGIC: &[u8; 8] = b"RDBDUMP\0";
+pub const VERSION: u32 = 1;
+
+const SECTION: u8 = 1;
+const RECORD: u8 = 2;
+const SECTION_END: u8 = 3;
+const TRAILER: u8 = 4;
+
+/// Dump file formats selectable with `--format`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DumpFormat {
+    /// Records printed according to `--output-format`
+    Text,
+    Binary,
+}
+
+impl DumpFormat {
+    pub const VARIANTS: &'static [&'static str] = &["text", "binary"];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DumpFormat::Text => "text",
+            DumpFormat::Binary => "binary",
+        }
+    }
+
+    /// Extension of the files in this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            DumpFormat::Text => "txt",
+            DumpFormat::Binary => "bin",
+        }
+    }
+}
+
+impl FromStr for DumpFormat {
+    type Err = SimpleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(DumpFormat::Text),
+            "binary" => Ok(DumpFormat::Binary),
+            _ => Err(SimpleError::new(format!("Unknown dump format: {}", s))),
+        }
+    }
+}
+
+pub fn format_arg() -> Arg<'static, 'static> {
+    Arg::with_name("format")
+        .long("format")
+        .help("Format of the dump file, binary dumps are lossless and list their column families up front")
+        .required(false)
+        .takes_value(true)
+        .possible_values(DumpFormat::VARIANTS)
+        .default_value("text")
+}
+
+fn corrupted(reason: &str) -> Box<dyn Error> {
+    Box::new(SimpleError::new(format!("Corrupted dump: {}", reason)))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Header {
+    /// Path of the dumped DB
+    pub source: String,
+    /// Sequence number of the DB when the dump was taken
+    pub sequence_number: u64,
+    /// Column families in the order of their sections
+    pub column_families: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Entry {
+    /// Following records belong to this column family
+    ColumnFamily(String),
+    Record(Vec<u8>, Vec<u8>),
+}
+
+pub struct Writer<W: Write> {
+    out: W,
+    hasher: crc32fast::Hasher,
+    column_family: Option<String>,
+    section_keys: u64,
+    total_keys: u64,
+    finished: bool,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(out: W, header: &Header) -> Result<Writer<W>, Box<dyn Error>> {
+        let mut writer = Writer {
+            out,
+            hasher: crc32fast::Hasher::new(),
+            column_family: None,
+            section_keys: 0,
+            total_keys: 0,
+            finished: false,
+        };
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_bytes(header.source.as_bytes())?;
+        writer.write_all(&header.sequence_number.to_le_bytes())?;
+        writer.write_all(&(header.column_families.len() as u32).to_le_bytes())?;
+        for name in &header.column_families {
+            writer.write_bytes(name.as_bytes())?;
+        }
+        Ok(writer)
+    }
+
+    fn write_all(&mut self, data: &[u8]) -> Result<(), Box<dyn Error>> {
+        self.hasher.update(data);
+        self.out.write_all(data)?;
+        Ok(())
+    }
+
+    fn write_bytes(&mut self, data: &[u8]) -> Result<(), Box<dyn Error>> {
+        if data.len() > u32::MAX as usize {
+            return Err(Box::new(SimpleError::new(format!(
+                "Cannot dump {} bytes long data",
+                data.len()
+            ))));
+        }
+        self.write_all(&(data.len() as u32).to_le_bytes())?;
+        self.write_all(data)
+    }
+
+    fn end_section(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.column_family.take().is_some() {
+            self.write_all(&[SECTION_END])?;
+            self.write_all(&self.section_keys.to_le_bytes())?;
+            self.section_keys = 0;
+        }
+        Ok(())
+    }
+
+    pub fn begin_column_family(&mut self, name: &str) -> Result<(), Box<dyn Error>> {
+        self.end_section()?;
+        self.write_all(&[SECTION])?;
+        self.write_bytes(name.as_bytes())?;
+        self.column_family = Some(name.to_string());
+        Ok(())
+    }
+
+    pub fn write(&mut self, key: &[u8], value: &[u8]) -> Result<(), Box<dyn Error>> {
+        if self.column_family.is_none() {
+            return Err(Box::new(SimpleError::new(
+                "Record written before its column family",
+            )));
+        }
+        self.write_all(&[RECORD])?;
+        self.write_bytes(key)?;
+        self.write_bytes(value)?;
+        self.section_keys += 1;
+        self.total_keys += 1;
+        Ok(())
+    }
+}
+
+impl<W: Write> Printer for Writer<W> {
+    /// Starts a new column family section whenever the column family changes
+    fn print(
+        &mut self,
+        column_family: Option<&str>,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<(), Box<dyn Error>> {
+        let name = column_family.unwrap_or(rocksdb::DEFAULT_COLUMN_FAMILY_NAME);
+        if self.column_family.as_deref() != Some(name) {
+            self.begin_column_family(name)?;
+        }
+        self.write(key, value)
+    }
+
+    /// Starts the section of the column family, so empty column families are dumped too
+    fn column_family(&mut self, column_family: &str) -> Result<(), Box<dyn Error>> {
+        if self.column_family.as_deref() != Some(column_family) {
+            self.begin_column_family(column_family)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.finished {
+            return Ok(());
+        }
+        self.end_section()?;
+        self.write_all(&[TRAILER])?;
+        self.write_all(&self.total_keys.to_le_bytes())?;
+        let checksum = self.hasher.clone().finalize();
+        self.out.write_all(&checksum.to_le_bytes())?;
+        self.out.flush()?;
+        self.finished = true;
+        Ok(())
+    }
+}
+
+pub struct Reader<R: Read> {
+    input: R,
+    hasher: crc32fast::Hasher,
+    header: Header,
+    in_section: bool,
+    section_keys: u64,
+    total_keys: u64,
+    finished: bool,
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(input: R) -> Result<Reader<R>, Box<dyn Error>> {
+        let mut reader = Reader {
+            input,
+            hasher: crc32fast::Hasher::new(),
+            header: Header {
+                source: String::new(),
+                sequence_number: 0,
+                column_families: Vec::new(),
+            },
+            in_section: false,
+            section_keys: 0,
+            total_keys: 0,
+            finished: false,
+        };
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(Box::new(SimpleError::new("Not a binary dump")));
+        }
+        let version = reader.read_u32()?;
+        if version != VERSION {
+            return Err(Box::new(SimpleError::new(format!(
+                "Unsupported binary dump version {}",
+                version
+            ))));
+        }
+        reader.header.source = String::from_utf8(reader.read_bytes()?)
+            .map_err(|_| corrupted("source path is not valid UTF-8"))?;
+        reader.header.sequence_number = reader.read_u64()?;
+        for _ in 0..reader.read_u32()? {
+            let name = String::from_utf8(reader.read_bytes()?)
+                .map_err(|_| corrupted("column family name is not valid UTF-8"))?;
+            reader.header.column_families.push(name);
+        }
+        Ok(reader)
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    fn read_exact(&mut self, data: &mut [u8]) -> Result<(), Box<dyn Error>> {
+        match self.input.read_exact(data) {
+            Ok(()) => {
+                self.hasher.update(data);
+                Ok(())
+            }
+            Err(error) if error.kind() == ErrorKind::UnexpectedEof => {
+                Err(corrupted("unexpected end of file"))
+            }
+            Err(error) => Err(Box::new(error)),
+        }
+    }
+
+    fn read_u8(&mut self) -> Result<u8, Box<dyn Error>> {
+        let mut data = [0u8; 1];
+        self.read_exact(&mut data)?;
+        Ok(data[0])
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Box<dyn Error>> {
+        let mut data = [0u8; 4];
+        self.read_exact(&mut data)?;
+        Ok(u32::from_le_bytes(data))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, Box<dyn Error>> {
+        let mut data = [0u8; 8];
+        self.read_exact(&mut data)?;
+        Ok(u64::from_le_bytes(data))
+    }
+
+    fn read_bytes(&mut self) -> Result<Vec<u8>, Box<dyn Error>> {
+        let len = self.read_u32()? as usize;
+        let mut data = Vec::new();
+        // Do not trust the length with a preallocation, a corrupted one would exhaust memory
+        (&mut self.input).take(len as u64).read_to_end(&mut data)?;
+        if data.len() != len {
+            return Err(corrupted("unexpected end of file"));
+        }
+        self.hasher.update(&data);
+        Ok(data)
+    }
+
+    /// Reads the next entry, `None` once the trailer is read and the checksum verified
+    pub fn next_entry(&mut self) -> Result<Option<Entry>, Box<dyn Error>> {
+        loop {
+            if self.finished {
+                return Ok(None);
+            }
+            match self.read_u8()? {
+                SECTION if !self.in_section => {
+                    let name = String::from_utf8(self.read_bytes()?)
+                        .map_err(|_| corrupted("column family name is not valid UTF-8"))?;
+                    if !self.header.column_families.contains(&name) {
+                        return Err(corrupted("column family missing from the header"));
+                    }
+                    self.in_section = true;
+                    return Ok(Some(Entry::ColumnFamily(name)));
+                }
+                RECORD if self.in_section => {
+                    let key = self.read_bytes()?;
+                    let value = self.read_bytes()?;
+                    self.section_keys += 1;
+                    self.total_keys += 1;
+                    return Ok(Some(Entry::Record(key, value)));
+                }
+                SECTION_END if self.in_section => {
+                    if self.read_u64()? != self.section_keys {
+                        return Err(corrupted("column family key count mismatch"));
+                    }
+                    self.in_section = false;
+                    self.section_keys = 0;
+                }
+                TRAILER if !self.in_section => {
+                    if self.read_u64()? != self.total_keys {
+                        return Err(corrupted("key count mismatch"));
+                    }
+                    let expected = self.hasher.clone().finalize();
+                    let mut checksum = [0u8; 4];
+                    self.input
+                        .read_exact(&mut checksum)
+                        .map_err(|_| corrupted("unexpected end of file"))?;
+                    if u32::from_le_bytes(checksum) != expected {
+                        return Err(corrupted("checksum mismatch"));
+                    }
+                    self.finished = true;
+                }
+                tag => return Err(corrupted(&format!("unexpected tag {}", tag))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> Header {
+        Header {
+            source: "/tmp/db".to_string(),
+            sequence_number: 42,
+            column_families: vec![
+                "default".to_string(),
+                "cf1".to_string(),
+                "empty".to_string(),
+            ],
+        }
+    }
+
+    fn dump(records: &[(&str, &[u8], &[u8])]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut writer = Writer::new(&mut data, &header()).unwrap();
+        for (cf, key, value) in records {
+            writer.print(Some(*cf), key, value).unwrap();
+        }
+        writer.finish().unwrap();
+        data
+    }
+
+    fn read_all(data: &[u8]) -> Result<Vec<Entry>, Box<dyn Error>> {
+        let mut reader = Reader::new(data)?;
+        assert_eq!(reader.header(), &header());
+        let mut entries = Vec::new();
+        while let Some(entry) = reader.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    #[test]
+    fn round_trip() {
+        let data = dump(&[
+            ("default", b"key ==> \n", b"\x00\xff"),
+            ("default", b"", b""),
+            ("cf1", b"k", b"v"),
+        ]);
+        assert_eq!(
+            read_all(&data).unwrap(),
+            vec![
+                Entry::ColumnFamily("default".to_string()),
+                Entry::Record(b"key ==> \n".to_vec(), b"\x00\xff".to_vec()),
+                Entry::Record(Vec::new(), Vec::new()),
+                Entry::ColumnFamily("cf1".to_string()),
+                Entry::Record(b"k".to_vec(), b"v".to_vec()),
+            ]
+        );
+        assert_eq!(read_all(&dump(&[])).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn empty_column_family() {
+        let mut data = Vec::new();
+        let mut writer = Writer::new(&mut data, &header()).unwrap();
+        writer.column_family("empty").unwrap();
+        writer.column_family("cf1").unwrap();
+        writer.print(Some("cf1"), b"k", b"v").unwrap();
+        writer.finish().unwrap();
+        assert_eq!(
+            read_all(&data).unwrap(),
+            vec![
+                Entry::ColumnFamily("empty".to_string()),
+                Entry::ColumnFamily("cf1".to_string()),
+                Entry::Record(b"k".to_vec(), b"v".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn detects_corruption() {
+        let data = dump(&[("default", b"key", b"value")]);
+        let mut flipped = data.clone();
+        // The value is followed by the section end, the trailer and the checksum
+        flipped[data.len() - 4 - 9 - 9 - 1] ^= 1;
+        assert!(read_all(&flipped)
+            .unwrap_err()
+            .to_string()
+            .contains("checksum mismatch"));
+        assert!(read_all(&data[..data.len() - 1])
+            .unwrap_err()
+            .to_string()
+            .contains("unexpected end of file"));
+        assert!(read_all(b"key ==> value\n").is_err());
+
+        let mut data = Vec::new();
+        let mut writer = Writer::new(&mut data, &header()).unwrap();
+        writer.print(Some("unlisted"), b"k", b"v").unwrap();
+        writer.finish().unwrap();
+        assert!(read_all(&data)
+            .unwrap_err()
+            .to_string()
+            .contains("column family missing from the header"));
+    }
+}
@@ -1,5 +1,8 @@
 mod cmd_parser;
 mod command;
+mod dumpfile;
+mod printer;
+mod schema;
 mod utils;
 use command::create;
 use std::error::Error;
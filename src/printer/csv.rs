@@ -0,0 +1,86 @@
+use super::{Encoding, Printer};
+use std::boxed::Box;
+use std::error::Error;
+use std::io::Write;
+
+/// Prints records as delimiter separated values with a `key,value` header
+pub struct CsvPrinter<'w> {
+    writer: csv::Writer<Box<dyn Write + 'w>>,
+    encoding: Encoding,
+    header_written: bool,
+}
+
+impl<'w> CsvPrinter<'w> {
+    pub fn new(out: Box<dyn Write + 'w>, encoding: Encoding, delimiter: u8) -> Self {
+        CsvPrinter {
+            writer: csv::WriterBuilder::new()
+                .delimiter(delimiter)
+                .from_writer(out),
+            encoding,
+            header_written: false,
+        }
+    }
+
+    fn write_header(&mut self, with_column_family: bool) -> Result<(), Box<dyn Error>> {
+        if !self.header_written {
+            if with_column_family {
+                self.writer
+                    .write_record(["column_family", "key", "value"])?;
+            } else {
+                self.writer.write_record(["key", "value"])?;
+            }
+            self.header_written = true;
+        }
+        Ok(())
+    }
+}
+
+impl<'w> Printer for CsvPrinter<'w> {
+    fn print(
+        &mut self,
+        column_family: Option<&str>,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<(), Box<dyn Error>> {
+        self.write_header(column_family.is_some())?;
+        if let Some(column_family) = column_family {
+            self.writer.write_field(column_family)?;
+        }
+        self.writer.write_record(&[
+            self.encoding.key(column_family, key),
+            self.encoding.value(column_family, key, value),
+        ])?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        self.write_header(false)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::print_all;
+    use super::super::{Encoding, OutputFormat};
+
+    #[test]
+    fn test_csv() {
+        let records: &[(Option<&str>, &[u8], &[u8])] =
+            &[(None, b"1111", b"aaaa"), (None, b"2,2", b"b\"b")];
+        assert_eq!(
+            print_all(OutputFormat::Csv, Encoding::default(), None, records),
+            "key,value\n1111,aaaa\n\"2,2\",\"b\"\"b\"\n"
+        );
+    }
+
+    #[test]
+    fn test_tsv() {
+        let records: &[(Option<&str>, &[u8], &[u8])] = &[(Some("cf1"), b"1111", b"aaaa")];
+        assert_eq!(
+            print_all(OutputFormat::Tsv, Encoding::default(), None, records),
+            "column_family\tkey\tvalue\ncf1\t1111\taaaa\n"
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use super::{Encoding, Printer};
+use serde_json::json;
+use std::boxed::Box;
+use std::error::Error;
+use std::io::Write;
+
+/// Prints records as `{"key": ..., "value": ...}` objects, either as a single
+/// JSON array or as one object per line (JSON Lines)
+pub struct JsonPrinter<'w> {
+    out: Box<dyn Write + 'w>,
+    encoding: Encoding,
+    lines: bool,
+    printed: usize,
+}
+
+impl<'w> JsonPrinter<'w> {
+    pub fn new(out: Box<dyn Write + 'w>, encoding: Encoding, lines: bool) -> Self {
+        JsonPrinter {
+            out,
+            encoding,
+            lines,
+            printed: 0,
+        }
+    }
+}
+
+impl<'w> Printer for JsonPrinter<'w> {
+    fn print(
+        &mut self,
+        column_family: Option<&str>,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<(), Box<dyn Error>> {
+        let mut record = json!({
+            "key": self.encoding.key(column_family, key),
+            "value": self.encoding.value(column_family, key, value),
+        });
+        if let Some(column_family) = column_family {
+            record["column_family"] = json!(column_family);
+        }
+        if self.lines {
+            writeln!(self.out, "{}", record)?;
+        } else {
+            let separator = if self.printed == 0 { "[\n" } else { ",\n" };
+            write!(self.out, "{}  {}", separator, record)?;
+        }
+        self.printed += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        if !self.lines {
+            if self.printed == 0 {
+                writeln!(self.out, "[]")?;
+            } else {
+                writeln!(self.out, "\n]")?;
+            }
+        }
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::print_all;
+    use super::super::{Encoding, OutputFormat};
+
+    #[test]
+    fn test_json() {
+        let records: &[(Option<&str>, &[u8], &[u8])] =
+            &[(None, b"1111", b"aaaa"), (None, b"2222", b"b\"b")];
+        assert_eq!(
+            print_all(OutputFormat::Json, Encoding::default(), None, records),
+            "[\n  {\"key\":\"1111\",\"value\":\"aaaa\"},\n  {\"key\":\"2222\",\"value\":\"b\\\"b\"}\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_json_empty() {
+        assert_eq!(
+            print_all(OutputFormat::Json, Encoding::default(), None, &[]),
+            "[]\n"
+        );
+    }
+
+    #[test]
+    fn test_jsonl() {
+        let records: &[(Option<&str>, &[u8], &[u8])] =
+            &[(None, b"1111", b"aaaa"), (Some("cf1"), b"2222", b"bbbb")];
+        assert_eq!(
+            print_all(OutputFormat::Jsonl, Encoding::default(), None, records),
+            "{\"key\":\"1111\",\"value\":\"aaaa\"}\n{\"column_family\":\"cf1\",\"key\":\"2222\",\"value\":\"bbbb\"}\n"
+        );
+    }
+}
@@ -0,0 +1,373 @@
+use crate::command::database;
+use crate::schema::Schema;
+use crate::utils;
+use crate::utils::codec::{self, Codec};
+use clap::ArgMatches;
+use simple_error::SimpleError;
+use std::boxed::Box;
+use std::error::Error;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::Arc;
+
+pub mod csv;
+pub mod json;
+pub mod plain;
+pub mod table;
+
+/// Output formats selectable with `--output-format`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Json,
+    Jsonl,
+    Csv,
+    Tsv,
+    Table,
+    Plain,
+}
+
+impl OutputFormat {
+    pub const VARIANTS: &'static [&'static str] =
+        &["json", "jsonl", "csv", "tsv", "table", "plain"];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Jsonl => "jsonl",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Table => "table",
+            OutputFormat::Plain => "plain",
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = SimpleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "jsonl" => Ok(OutputFormat::Jsonl),
+            "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
+            "table" => Ok(OutputFormat::Table),
+            "plain" => Ok(OutputFormat::Plain),
+            _ => Err(SimpleError::new(format!("Unknown output format: {}", s))),
+        }
+    }
+}
+
+/// Value encodings of `get` selectable with `--value-encoding`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueEncoding {
+    /// UTF-8 if the value is printable text, hex otherwise
+    Auto,
+    /// UTF-8, failing on invalid sequences
+    Utf8,
+    /// UTF-8 with invalid sequences replaced
+    Lossy,
+    Hex,
+    Base64,
+    /// Non-printable bytes escaped as `\xNN`
+    Escaped,
+    /// The exact bytes without any printer
+    Raw,
+}
+
+impl ValueEncoding {
+    pub const VARIANTS: &'static [&'static str] =
+        &["auto", "utf8", "lossy", "hex", "base64", "escaped", "raw"];
+
+    /// Renders the value as text, raw values are rendered lossy
+    pub fn render(self, data: &[u8]) -> Result<String, Box<dyn Error>> {
+        Ok(match self {
+            ValueEncoding::Auto => {
+                if is_binary(data) {
+                    utils::hex::encode(data)
+                } else {
+                    String::from_utf8_lossy(data).into()
+                }
+            }
+            ValueEncoding::Utf8 => match String::from_utf8(data.to_vec()) {
+                Ok(text) => text,
+                Err(error) => {
+                    return Err(Box::new(SimpleError::new(format!(
+                        "Value is not valid UTF-8 ({}), choose another --value-encoding",
+                        error.utf8_error()
+                    ))))
+                }
+            },
+            ValueEncoding::Lossy | ValueEncoding::Raw => String::from_utf8_lossy(data).into(),
+            ValueEncoding::Hex => utils::hex::encode(data),
+            ValueEncoding::Base64 => base64::encode(data),
+            ValueEncoding::Escaped => data
+                .iter()
+                .flat_map(|b| std::ascii::escape_default(*b))
+                .map(char::from)
+                .collect(),
+        })
+    }
+}
+
+impl FromStr for ValueEncoding {
+    type Err = SimpleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ValueEncoding::Auto),
+            "utf8" => Ok(ValueEncoding::Utf8),
+            "lossy" => Ok(ValueEncoding::Lossy),
+            "hex" => Ok(ValueEncoding::Hex),
+            "base64" => Ok(ValueEncoding::Base64),
+            "escaped" => Ok(ValueEncoding::Escaped),
+            "raw" => Ok(ValueEncoding::Raw),
+            _ => Err(SimpleError::new(format!("Unknown value encoding: {}", s))),
+        }
+    }
+}
+
+/// Data is binary unless it is valid UTF-8 without control characters other than whitespace
+pub fn is_binary(data: &[u8]) -> bool {
+    match std::str::from_utf8(data) {
+        Ok(text) => text
+            .chars()
+            .any(|c| c.is_control() && !c.is_ascii_whitespace()),
+        Err(_) => true,
+    }
+}
+
+static UTF8: Codec = Codec::Utf8;
+
+/// Defines how raw keys and values are turned into printable strings and back.
+///
+/// Codecs given on the command line apply to every key or value, otherwise the schema picks
+/// them by column family and key prefix. Keys and values not covered by either are UTF-8.
+#[derive(Debug, Clone, Default)]
+pub struct Encoding {
+    pub key: Option<Codec>,
+    pub value: Option<Codec>,
+    pub schema: Option<Arc<Schema>>,
+    /// Column family of the records printed without one
+    pub column_family: String,
+}
+
+impl Encoding {
+    /// The codecs chosen with `--key-codec` and `--value-codec` or their hex shorthands,
+    /// falling back to the schema
+    pub fn from_matches(
+        matches: &ArgMatches,
+        subcommand_matches: &ArgMatches,
+    ) -> Result<Encoding, Box<dyn Error>> {
+        Ok(Encoding {
+            key: codec::key_codec(subcommand_matches)?,
+            value: codec::value_codec(subcommand_matches)?,
+            schema: Schema::from_matches(matches)?.map(Arc::new),
+            column_family: database::column_families(matches)[0].to_string(),
+        })
+    }
+
+    fn column_family<'s>(&'s self, column_family: Option<&'s str>) -> &'s str {
+        column_family.unwrap_or(&self.column_family)
+    }
+
+    pub fn key_codec(&self, column_family: Option<&str>, key: &[u8]) -> &Codec {
+        let column_family = self.column_family(column_family);
+        self.key
+            .as_ref()
+            .or_else(|| {
+                let rule = self.schema.as_ref()?.rule(column_family, key)?;
+                rule.key.as_ref()
+            })
+            .unwrap_or(&UTF8)
+    }
+
+    pub fn value_codec(&self, column_family: Option<&str>, key: &[u8]) -> &Codec {
+        let column_family = self.column_family(column_family);
+        self.value
+            .as_ref()
+            .or_else(|| {
+                let rule = self.schema.as_ref()?.rule(column_family, key)?;
+                rule.value.as_ref()
+            })
+            .unwrap_or(&UTF8)
+    }
+
+    pub fn key(&self, column_family: Option<&str>, key: &[u8]) -> String {
+        self.key_codec(column_family, key).encode(key)
+    }
+
+    pub fn value(&self, column_family: Option<&str>, key: &[u8], value: &[u8]) -> String {
+        self.value_codec(column_family, key).encode(value)
+    }
+
+    /// The decoded key as JSON, as queried by `--where` and `--select`
+    pub fn key_json(&self, column_family: Option<&str>, key: &[u8]) -> serde_json::Value {
+        self.key_codec(column_family, key).to_json(key)
+    }
+
+    /// The decoded value as JSON, as queried by `--where` and `--select`
+    pub fn value_json(
+        &self,
+        column_family: Option<&str>,
+        key: &[u8],
+        value: &[u8],
+    ) -> serde_json::Value {
+        self.value_codec(column_family, key).to_json(value)
+    }
+
+    /// Turns a typed key into bytes
+    pub fn decode_key(
+        &self,
+        column_family: Option<&str>,
+        text: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        if let Some(codec) = &self.key {
+            return codec.decode(text);
+        }
+        let column_family = self.column_family(column_family);
+        match self
+            .schema
+            .as_ref()
+            .and_then(|schema| schema.decode_key(column_family, text))
+        {
+            Some(key) => Ok(key),
+            None => UTF8.decode(text),
+        }
+    }
+
+    /// Turns a typed value of the key into bytes
+    pub fn decode_value(
+        &self,
+        column_family: Option<&str>,
+        key: &[u8],
+        text: &str,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        self.value_codec(column_family, key).decode(text)
+    }
+}
+
+pub trait Printer {
+    /// Prints a single record, the column family is given only when several of them are printed
+    fn print(
+        &mut self,
+        column_family: Option<&str>,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<(), Box<dyn Error>>;
+
+    /// Writes whatever the format needs after the last record and flushes the output
+    fn finish(&mut self) -> Result<(), Box<dyn Error>>;
+
+    /// Announces the records of a column family, formats that keep column families record it
+    /// even if no record follows
+    fn column_family(&mut self, _column_family: &str) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+/// Creates a printer for the given format.
+///
+/// `delimiter` separates key and value in the plain format, `None` makes it print values only.
+pub fn create<'w>(
+    format: OutputFormat,
+    out: Box<dyn Write + 'w>,
+    encoding: Encoding,
+    delimiter: Option<&'static str>,
+) -> Box<dyn Printer + 'w> {
+    match format {
+        OutputFormat::Json => Box::new(json::JsonPrinter::new(out, encoding, false)),
+        OutputFormat::Jsonl => Box::new(json::JsonPrinter::new(out, encoding, true)),
+        OutputFormat::Csv => Box::new(csv::CsvPrinter::new(out, encoding, b',')),
+        OutputFormat::Tsv => Box::new(csv::CsvPrinter::new(out, encoding, b'\t')),
+        OutputFormat::Table => Box::new(table::TablePrinter::new(out, encoding)),
+        OutputFormat::Plain => Box::new(plain::PlainPrinter::new(out, encoding, delimiter)),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Writer that keeps everything written to it, so tests can inspect the output
+    #[derive(Clone, Default)]
+    pub struct Output(Rc<RefCell<Vec<u8>>>);
+
+    impl Output {
+        pub fn contents(&self) -> String {
+            String::from_utf8(self.0.borrow().clone()).unwrap()
+        }
+    }
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    pub fn print_all(
+        format: OutputFormat,
+        encoding: Encoding,
+        delimiter: Option<&'static str>,
+        records: &[(Option<&str>, &[u8], &[u8])],
+    ) -> String {
+        let output = Output::default();
+        let mut printer = create(format, Box::new(output.clone()), encoding, delimiter);
+        for (column_family, key, value) in records {
+            printer.print(*column_family, key, value).unwrap();
+        }
+        printer.finish().unwrap();
+        output.contents()
+    }
+
+    #[test]
+    fn test_parse_output_format() {
+        for name in OutputFormat::VARIANTS {
+            assert_eq!(name.parse::<OutputFormat>().unwrap().name(), *name);
+        }
+        assert!("yaml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_value_encoding() {
+        for name in ValueEncoding::VARIANTS {
+            assert!(name.parse::<ValueEncoding>().is_ok());
+        }
+        let binary = b"\x00\xffhi";
+        assert!(is_binary(binary));
+        assert!(!is_binary("привет\tworld\n".as_bytes()));
+        assert_eq!(ValueEncoding::Auto.render(b"hello").unwrap(), "hello");
+        assert_eq!(ValueEncoding::Auto.render(binary).unwrap(), "0x00ff6869");
+        assert!(ValueEncoding::Utf8.render(binary).is_err());
+        assert_eq!(
+            ValueEncoding::Lossy.render(binary).unwrap(),
+            "\u{0}\u{fffd}hi"
+        );
+        assert_eq!(ValueEncoding::Base64.render(binary).unwrap(), "AP9oaQ==");
+        assert_eq!(
+            ValueEncoding::Escaped.render(binary).unwrap(),
+            "\\x00\\xffhi"
+        );
+    }
+
+    #[test]
+    fn test_encoding() {
+        let encoding = Encoding {
+            key: Some(Codec::Hex),
+            ..Default::default()
+        };
+        assert_eq!(encoding.key(None, b"hello"), "0x68656c6c6f");
+        assert_eq!(encoding.value(None, b"hello", b"hello"), "hello");
+        assert_eq!(encoding.decode_key(None, "0x6869").unwrap(), b"hi");
+        assert_eq!(
+            encoding.decode_value(None, b"hi", "0x6869").unwrap(),
+            b"0x6869"
+        );
+    }
+}
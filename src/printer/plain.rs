@@ -0,0 +1,109 @@
+use super::{Encoding, Printer};
+use std::boxed::Box;
+use std::error::Error;
+use std::io::Write;
+
+/// Prints one `key<delimiter>value` line per record, or only the value if there is no delimiter
+pub struct PlainPrinter<'w> {
+    out: Box<dyn Write + 'w>,
+    encoding: Encoding,
+    delimiter: Option<&'static str>,
+}
+
+impl<'w> PlainPrinter<'w> {
+    pub fn new(
+        out: Box<dyn Write + 'w>,
+        encoding: Encoding,
+        delimiter: Option<&'static str>,
+    ) -> Self {
+        PlainPrinter {
+            out,
+            encoding,
+            delimiter,
+        }
+    }
+}
+
+impl<'w> Printer for PlainPrinter<'w> {
+    fn print(
+        &mut self,
+        column_family: Option<&str>,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<(), Box<dyn Error>> {
+        match self.delimiter {
+            None => writeln!(
+                self.out,
+                "{}",
+                self.encoding.value(column_family, key, value)
+            )?,
+            Some(delimiter) => {
+                if let Some(column_family) = column_family {
+                    write!(self.out, "{}{}", column_family, delimiter)?;
+                }
+                writeln!(
+                    self.out,
+                    "{}{}{}",
+                    self.encoding.key(column_family, key),
+                    delimiter,
+                    self.encoding.value(column_family, key, value)
+                )?
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::print_all;
+    use super::super::{Encoding, OutputFormat};
+    use crate::utils::codec::Codec;
+
+    #[test]
+    fn test_plain_with_delimiter() {
+        let records: &[(Option<&str>, &[u8], &[u8])] =
+            &[(None, b"1111", b"aaaa"), (None, b"2222", b"bbbb")];
+        assert_eq!(
+            print_all(
+                OutputFormat::Plain,
+                Encoding::default(),
+                Some(" : "),
+                records
+            ),
+            "1111 : aaaa\n2222 : bbbb\n"
+        );
+    }
+
+    #[test]
+    fn test_plain_with_column_family() {
+        let records: &[(Option<&str>, &[u8], &[u8])] = &[(Some("cf1"), b"1111", b"aaaa")];
+        assert_eq!(
+            print_all(
+                OutputFormat::Plain,
+                Encoding::default(),
+                Some(" : "),
+                records
+            ),
+            "cf1 : 1111 : aaaa\n"
+        );
+    }
+
+    #[test]
+    fn test_plain_value_only() {
+        let records: &[(Option<&str>, &[u8], &[u8])] = &[(None, b"1111", b"aaaa")];
+        let encoding = Encoding {
+            value: Some(Codec::Hex),
+            ..Default::default()
+        };
+        assert_eq!(
+            print_all(OutputFormat::Plain, encoding, None, records),
+            "0x61616161\n"
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use super::{Encoding, Printer};
+use std::boxed::Box;
+use std::cmp;
+use std::error::Error;
+use std::io::Write;
+
+/// Prints records as an aligned table. Column widths depend on every row,
+/// so the rows are kept in memory until `finish` is called.
+pub struct TablePrinter<'w> {
+    out: Box<dyn Write + 'w>,
+    encoding: Encoding,
+    rows: Vec<Vec<String>>,
+    with_column_family: bool,
+}
+
+impl<'w> TablePrinter<'w> {
+    pub fn new(out: Box<dyn Write + 'w>, encoding: Encoding) -> Self {
+        TablePrinter {
+            out,
+            encoding,
+            rows: Vec::new(),
+            with_column_family: false,
+        }
+    }
+
+    fn write_separator(&mut self, widths: &[usize]) -> Result<(), Box<dyn Error>> {
+        for width in widths {
+            write!(self.out, "+{}", "-".repeat(width + 2))?;
+        }
+        writeln!(self.out, "+")?;
+        Ok(())
+    }
+
+    fn write_row(&mut self, row: &[String], widths: &[usize]) -> Result<(), Box<dyn Error>> {
+        for (cell, width) in row.iter().zip(widths) {
+            write!(
+                self.out,
+                "| {}{} ",
+                cell,
+                " ".repeat(width - cell.chars().count())
+            )?;
+        }
+        writeln!(self.out, "|")?;
+        Ok(())
+    }
+}
+
+impl<'w> Printer for TablePrinter<'w> {
+    fn print(
+        &mut self,
+        column_family: Option<&str>,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<(), Box<dyn Error>> {
+        let mut row = Vec::with_capacity(3);
+        if let Some(column_family) = column_family {
+            self.with_column_family = true;
+            row.push(column_family.to_string());
+        }
+        row.push(self.encoding.key(column_family, key));
+        row.push(self.encoding.value(column_family, key, value));
+        self.rows.push(row);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut header = vec!["key".to_string(), "value".to_string()];
+        if self.with_column_family {
+            header.insert(0, "column_family".to_string());
+        }
+        let mut widths: Vec<usize> = header.iter().map(|cell| cell.len()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row.iter()) {
+                *width = cmp::max(*width, cell.chars().count());
+            }
+        }
+        let rows = std::mem::take(&mut self.rows);
+        self.write_separator(&widths)?;
+        self.write_row(&header, &widths)?;
+        self.write_separator(&widths)?;
+        for row in &rows {
+            self.write_row(row, &widths)?;
+        }
+        self.write_separator(&widths)?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::print_all;
+    use super::super::{Encoding, OutputFormat};
+
+    #[test]
+    fn test_table() {
+        let records: &[(Option<&str>, &[u8], &[u8])] =
+            &[(None, b"1", b"aaaaaaa"), (None, b"2222", b"b")];
+        assert_eq!(
+            print_all(OutputFormat::Table, Encoding::default(), None, records),
+            "+------+---------+
+| key  | value   |
++------+---------+
+| 1    | aaaaaaa |
+| 2222 | b       |
++------+---------+
+"
+        );
+    }
+
+    #[test]
+    fn test_table_with_column_family() {
+        let records: &[(Option<&str>, &[u8], &[u8])] = &[(Some("cf1"), b"1", b"a")];
+        assert_eq!(
+            print_all(OutputFormat::Table, Encoding::default(), None, records),
+            "+---------------+-----+-------+
+| column_family | key | value |
++---------------+-----+-------+
+| cf1           | 1   | a     |
++---------------+-----+-------+
+"
+        );
+    }
+}
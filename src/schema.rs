@@ -0,0 +1,255 @@
+use crate::utils::codec::Codec;
+use crate::utils::{self, protobuf};
+use clap::{Arg, ArgMatches};
+use serde::Deserialize;
+use simple_error::SimpleError;
+use std::boxed::Box;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Name of the schema looked up in the DB directory and then in the current directory
+pub const SCHEMA_FILE: &str = ".rdbrowser.toml";
+
+/// A `[[rule]]` of the schema file as written
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleConfig {
+    column_family: Option<String>,
+    prefix: Option<String>,
+    prefix_hex: Option<String>,
+    key_codec: Option<String>,
+    key_format: Option<String>,
+    value_codec: Option<String>,
+    bincode_schema: Option<String>,
+    proto_descriptor: Option<String>,
+    proto_message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SchemaConfig {
+    #[serde(default)]
+    rule: Vec<RuleConfig>,
+}
+
+/// Codecs of the keys starting with the prefix in the column family
+#[derive(Debug)]
+pub struct Rule {
+    /// Any column family if not given
+    column_family: Option<String>,
+    prefix: Vec<u8>,
+    pub key: Option<Codec>,
+    pub value: Option<Codec>,
+}
+
+impl Rule {
+    fn from_config(config: RuleConfig, directory: &Path) -> Result<Rule, Box<dyn Error>> {
+        let prefix = match (config.prefix, config.prefix_hex) {
+            (Some(_), Some(_)) => {
+                return Err(Box::new(SimpleError::new(
+                    "prefix and prefix_hex can not be given together",
+                )))
+            }
+            (Some(prefix), None) => prefix.into_bytes(),
+            (None, Some(prefix)) => utils::hex::decode(&prefix)?,
+            (None, None) => Vec::new(),
+        };
+        let key = match (config.key_codec, config.key_format) {
+            (Some(_), Some(_)) => {
+                return Err(Box::new(SimpleError::new(
+                    "key_codec and key_format can not be given together",
+                )))
+            }
+            (Some(codec), None) => Some(codec.parse()?),
+            (None, Some(format)) => Some(Codec::Template(format.parse()?)),
+            (None, None) => None,
+        };
+        let value = match (
+            config.value_codec,
+            config.bincode_schema,
+            config.proto_descriptor,
+            config.proto_message,
+        ) {
+            (codec, None, None, None) => match codec {
+                Some(codec) => Some(codec.parse()?),
+                None => None,
+            },
+            (None, Some(schema), None, None) => Some(Codec::Bincode(schema.parse()?)),
+            (None, None, Some(descriptor), Some(message)) => {
+                // Descriptors are shared along with the schema, so they are relative to it
+                let descriptor = directory.join(descriptor);
+                Some(Codec::Protobuf(protobuf::message_descriptor(
+                    &descriptor.to_string_lossy(),
+                    &message,
+                )?))
+            }
+            _ => {
+                return Err(Box::new(SimpleError::new(
+                    "Expected either value_codec, bincode_schema or \
+                     proto_descriptor with proto_message",
+                )))
+            }
+        };
+        Ok(Rule {
+            column_family: config.column_family,
+            prefix,
+            key,
+            value,
+        })
+    }
+
+    fn matches_column_family(&self, column_family: &str) -> bool {
+        self.column_family
+            .as_deref()
+            .is_none_or(|name| name == column_family)
+    }
+}
+
+/// Rules of the schema file mapping column families and key prefixes to codecs.
+///
+/// The first rule matching a key is applied.
+#[derive(Debug)]
+pub struct Schema {
+    rules: Vec<Rule>,
+}
+
+impl Schema {
+    pub fn load(path: &Path) -> Result<Schema, Box<dyn Error>> {
+        let text = std::fs::read_to_string(path)?;
+        let config: SchemaConfig = match toml::from_str(&text) {
+            Ok(config) => config,
+            Err(error) => {
+                return Err(Box::new(SimpleError::new(format!(
+                    "Invalid schema {}: {}",
+                    path.display(),
+                    error
+                ))))
+            }
+        };
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        let rules = config
+            .rule
+            .into_iter()
+            .enumerate()
+            .map(|(i, rule)| {
+                Rule::from_config(rule, directory).map_err(|error| {
+                    SimpleError::new(format!(
+                        "Invalid rule {} of schema {}: {}",
+                        i + 1,
+                        path.display(),
+                        error
+                    ))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Schema { rules })
+    }
+
+    /// Loads the schema given with `--schema` or found next to the DB or in the current directory
+    pub fn from_matches(matches: &ArgMatches) -> Result<Option<Schema>, Box<dyn Error>> {
+        if let Some(path) = matches.value_of("schema") {
+            return Ok(Some(Schema::load(Path::new(path))?));
+        }
+        let candidates = [
+            Path::new(matches.value_of("db").unwrap()).join(SCHEMA_FILE),
+            PathBuf::from(SCHEMA_FILE),
+        ];
+        match candidates.iter().find(|path| path.is_file()) {
+            Some(path) => Ok(Some(Schema::load(path)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// The rule of the key
+    pub fn rule(&self, column_family: &str, key: &[u8]) -> Option<&Rule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches_column_family(column_family) && key.starts_with(&rule.prefix))
+    }
+
+    /// Turns the typed key into bytes with the key codec of the first rule it fits
+    pub fn decode_key(&self, column_family: &str, text: &str) -> Option<Vec<u8>> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches_column_family(column_family))
+            .filter_map(|rule| {
+                let key = rule
+                    .key
+                    .as_ref()
+                    .unwrap_or(&Codec::Utf8)
+                    .decode(text)
+                    .ok()?;
+                if key.starts_with(&rule.prefix) {
+                    Some(key)
+                } else {
+                    None
+                }
+            })
+            .next()
+    }
+}
+
+pub fn schema_arg() -> Arg<'static, 'static> {
+    Arg::with_name("schema")
+        .long("schema")
+        .value_name("FILE")
+        .help("Schema mapping column families and key prefixes to codecs, .rdbrowser.toml in the DB or current directory by default")
+        .required(false)
+        .takes_value(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(text: &str) -> Result<Schema, Box<dyn Error>> {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SCHEMA_FILE);
+        std::fs::write(&path, text).unwrap();
+        Schema::load(&path)
+    }
+
+    #[test]
+    fn test_rules() {
+        let schema = schema(
+            r#"
+            [[rule]]
+            column_family = "users"
+            prefix_hex = "0x01"
+            key_format = "u8,u64be"
+            value_codec = "json"
+
+            [[rule]]
+            prefix = "cfg:"
+            value_codec = "hex"
+            "#,
+        )
+        .unwrap();
+        let user = schema.rule("users", b"\x01\x00").unwrap();
+        assert_eq!(
+            user.value,
+            Some(Codec::Serialized(utils::serialized::Serialization::Json))
+        );
+        assert!(schema.rule("default", b"\x01\x00").is_none());
+        let config = schema.rule("default", b"cfg:a").unwrap();
+        assert_eq!(config.key, None);
+        assert_eq!(config.value, Some(Codec::Hex));
+
+        assert_eq!(
+            schema.decode_key("users", "1,2").unwrap(),
+            b"\x01\x00\x00\x00\x00\x00\x00\x00\x02"
+        );
+        // The key of the first rule does not start with its prefix
+        assert_eq!(schema.decode_key("users", "cfg:a").unwrap(), b"cfg:a");
+        assert_eq!(schema.decode_key("default", "other"), None);
+    }
+
+    #[test]
+    fn test_invalid_schema() {
+        assert!(schema("[[rule]]\nprefix = \"a\"\nprefix_hex = \"0x61\"").is_err());
+        assert!(schema("[[rule]]\nvalue_codec = \"utf16\"").is_err());
+        assert!(schema("[[rule]]\nproto_message = \"test.User\"").is_err());
+        assert!(schema("[[rule]]\ncolor = \"red\"").is_err());
+        assert!(schema("[[rule]\n").is_err());
+    }
+}
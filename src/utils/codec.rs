@@ -0,0 +1,463 @@
+use crate::utils;
+use crate::utils::key_format::KeyFormat;
+use crate::utils::protobuf;
+use crate::utils::serialized::{BincodeSchema, Serialization};
+use clap::{Arg, ArgMatches};
+use prost_reflect::MessageDescriptor;
+use serde_json::Value;
+use simple_error::SimpleError;
+use std::boxed::Box;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::str::FromStr;
+
+/// Conversion between raw keys or values and the text typed or printed for them,
+/// selectable with `--key-codec` and `--value-codec`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Codec {
+    #[default]
+    Utf8,
+    Hex,
+    Base64,
+    /// URL safe base64 without padding
+    Base64Url,
+    /// C-style escapes such as `\n` and `\xNN`
+    Escaped,
+    /// The text is a path of a file holding the raw bytes, for input only
+    File,
+    U32Be,
+    U32Le,
+    U64Be,
+    U64Le,
+    I64Be,
+    I64Le,
+    /// Unsigned LEB128 integer
+    Varint,
+    /// JSON, MessagePack or CBOR value, typed and printed as JSON
+    Serialized(Serialization),
+    /// Struct serialized with bincode, typed and printed as a JSON object
+    Bincode(BincodeSchema),
+    /// Composite key given with `--key-format`
+    Template(KeyFormat),
+    /// Protobuf message given with `--proto-message`, typed and printed as JSON
+    Protobuf(MessageDescriptor),
+}
+
+impl Codec {
+    pub const VARIANTS: &'static [&'static str] = &[
+        "utf8",
+        "hex",
+        "base64",
+        "base64url",
+        "escaped",
+        "file",
+        "u32be",
+        "u32le",
+        "u64be",
+        "u64le",
+        "i64be",
+        "i64le",
+        "varint",
+        "json",
+        "msgpack",
+        "cbor",
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Codec::Utf8 => "utf8",
+            Codec::Hex => "hex",
+            Codec::Base64 => "base64",
+            Codec::Base64Url => "base64url",
+            Codec::Escaped => "escaped",
+            Codec::File => "file",
+            Codec::U32Be => "u32be",
+            Codec::U32Le => "u32le",
+            Codec::U64Be => "u64be",
+            Codec::U64Le => "u64le",
+            Codec::I64Be => "i64be",
+            Codec::I64Le => "i64le",
+            Codec::Varint => "varint",
+            Codec::Serialized(Serialization::Json) => "json",
+            Codec::Serialized(Serialization::MsgPack) => "msgpack",
+            Codec::Serialized(Serialization::Cbor) => "cbor",
+            Codec::Bincode(_) => "bincode",
+            Codec::Template(_) => "template",
+            Codec::Protobuf(_) => "protobuf",
+        }
+    }
+
+    /// Whether `encode` prints data it can not represent in hex
+    fn falls_back_to_hex(&self) -> bool {
+        !matches!(
+            self,
+            Codec::Utf8
+                | Codec::Hex
+                | Codec::File
+                | Codec::Base64
+                | Codec::Base64Url
+                | Codec::Escaped
+        )
+    }
+
+    /// Turns the typed text into raw bytes, hex printed by `encode` for data the codec can not
+    /// represent is accepted too, so dumps round-trip
+    pub fn decode(&self, text: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        match self.decode_typed(text) {
+            Err(_) if self.falls_back_to_hex() && text.starts_with("0x") => {
+                Ok(utils::hex::decode(text)?)
+            }
+            result => result,
+        }
+    }
+
+    fn decode_typed(&self, text: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(match self {
+            Codec::Utf8 => text.as_bytes().to_vec(),
+            Codec::Hex => utils::hex::decode(text)?,
+            Codec::Base64 => base64::decode(text)?,
+            Codec::Base64Url => {
+                base64::decode_config(text.trim_end_matches('='), base64::URL_SAFE_NO_PAD)?
+            }
+            Codec::Escaped => unescape(text)?,
+            Codec::File => std::fs::read(text)?,
+            Codec::U32Be => text.parse::<u32>()?.to_be_bytes().to_vec(),
+            Codec::U32Le => text.parse::<u32>()?.to_le_bytes().to_vec(),
+            Codec::U64Be => text.parse::<u64>()?.to_be_bytes().to_vec(),
+            Codec::U64Le => text.parse::<u64>()?.to_le_bytes().to_vec(),
+            Codec::I64Be => text.parse::<i64>()?.to_be_bytes().to_vec(),
+            Codec::I64Le => text.parse::<i64>()?.to_le_bytes().to_vec(),
+            Codec::Varint => {
+                let mut number = text.parse::<u64>()?;
+                let mut bytes = Vec::new();
+                loop {
+                    let byte = (number & 0x7f) as u8;
+                    number >>= 7;
+                    if number == 0 {
+                        bytes.push(byte);
+                        break;
+                    }
+                    bytes.push(byte | 0x80);
+                }
+                bytes
+            }
+            Codec::Serialized(serialization) => serialization.serialize(text)?,
+            Codec::Bincode(schema) => schema.serialize(text)?,
+            Codec::Template(format) => format.parse(text)?,
+            Codec::Protobuf(descriptor) => protobuf::from_json(descriptor, text)?,
+        })
+    }
+
+    /// Turns raw bytes into printable text.
+    ///
+    /// Data the codec can not represent, e.g. integers of a wrong size, is printed in hex.
+    pub fn encode(&self, data: &[u8]) -> String {
+        let number = match self {
+            Codec::Utf8 => return String::from_utf8_lossy(data).into(),
+            Codec::Hex | Codec::File => return utils::hex::encode(data),
+            Codec::Base64 => return base64::encode(data),
+            Codec::Base64Url => return base64::encode_config(data, base64::URL_SAFE_NO_PAD),
+            Codec::Escaped => {
+                return data
+                    .iter()
+                    .flat_map(|b| std::ascii::escape_default(*b))
+                    .map(char::from)
+                    .collect()
+            }
+            Codec::U32Be => <[u8; 4]>::try_from(data)
+                .ok()
+                .map(|bytes| u32::from_be_bytes(bytes).to_string()),
+            Codec::U32Le => <[u8; 4]>::try_from(data)
+                .ok()
+                .map(|bytes| u32::from_le_bytes(bytes).to_string()),
+            Codec::U64Be => <[u8; 8]>::try_from(data)
+                .ok()
+                .map(|bytes| u64::from_be_bytes(bytes).to_string()),
+            Codec::U64Le => <[u8; 8]>::try_from(data)
+                .ok()
+                .map(|bytes| u64::from_le_bytes(bytes).to_string()),
+            Codec::I64Be => <[u8; 8]>::try_from(data)
+                .ok()
+                .map(|bytes| i64::from_be_bytes(bytes).to_string()),
+            Codec::I64Le => <[u8; 8]>::try_from(data)
+                .ok()
+                .map(|bytes| i64::from_le_bytes(bytes).to_string()),
+            Codec::Varint => decode_varint(data).map(|number| number.to_string()),
+            Codec::Serialized(serialization) => serialization.to_json(data),
+            Codec::Bincode(schema) => schema.to_json(data),
+            Codec::Template(format) => format.format(data),
+            Codec::Protobuf(descriptor) => protobuf::to_json(descriptor, data),
+        };
+        number.unwrap_or_else(|| utils::hex::encode(data))
+    }
+
+    /// Turns raw bytes into a JSON value, so numbers and structured data can be queried
+    pub fn to_json(&self, data: &[u8]) -> Value {
+        let value = match self {
+            Codec::Utf8
+            | Codec::Hex
+            | Codec::File
+            | Codec::Base64
+            | Codec::Base64Url
+            | Codec::Escaped => None,
+            Codec::Template(format) => format.to_json(data),
+            // The rest are printed as JSON unless they fall back to hex
+            _ => serde_json::from_str(&self.encode(data)).ok(),
+        };
+        value.unwrap_or_else(|| Value::String(self.encode(data)))
+    }
+}
+
+impl FromStr for Codec {
+    type Err = SimpleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "utf8" => Ok(Codec::Utf8),
+            "hex" => Ok(Codec::Hex),
+            "base64" => Ok(Codec::Base64),
+            "base64url" => Ok(Codec::Base64Url),
+            "escaped" => Ok(Codec::Escaped),
+            "file" => Ok(Codec::File),
+            "u32be" => Ok(Codec::U32Be),
+            "u32le" => Ok(Codec::U32Le),
+            "u64be" => Ok(Codec::U64Be),
+            "u64le" => Ok(Codec::U64Le),
+            "i64be" => Ok(Codec::I64Be),
+            "i64le" => Ok(Codec::I64Le),
+            "varint" => Ok(Codec::Varint),
+            "json" => Ok(Codec::Serialized(Serialization::Json)),
+            "msgpack" => Ok(Codec::Serialized(Serialization::MsgPack)),
+            "cbor" => Ok(Codec::Serialized(Serialization::Cbor)),
+            _ => Err(SimpleError::new(format!("Unknown codec: {}", s))),
+        }
+    }
+}
+
+/// Decodes a varint taking exactly all the bytes
+fn decode_varint(data: &[u8]) -> Option<u64> {
+    let mut number = 0u64;
+    for (i, byte) in data.iter().enumerate() {
+        if i >= 10 {
+            return None;
+        }
+        number |= u64::from(byte & 0x7f).checked_shl(7 * i as u32)?;
+        if byte & 0x80 == 0 {
+            return if i + 1 == data.len() {
+                Some(number)
+            } else {
+                None
+            };
+        }
+    }
+    None
+}
+
+fn unescape(text: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut bytes = Vec::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buffer = [0u8; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            continue;
+        }
+        let byte = match chars.next() {
+            Some('n') => b'\n',
+            Some('r') => b'\r',
+            Some('t') => b'\t',
+            Some('0') => b'\0',
+            Some('\\') => b'\\',
+            Some('\'') => b'\'',
+            Some('"') => b'"',
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(byte) if digits.len() == 2 => byte,
+                    _ => {
+                        return Err(Box::new(SimpleError::new(format!(
+                            "Invalid escape \\x{} in {}",
+                            digits, text
+                        ))))
+                    }
+                }
+            }
+            other => {
+                return Err(Box::new(SimpleError::new(format!(
+                    "Invalid escape \\{} in {}",
+                    other.map(String::from).unwrap_or_default(),
+                    text
+                ))))
+            }
+        };
+        bytes.push(byte);
+    }
+    Ok(bytes)
+}
+
+/// `--hex` shorthand for hex keys and values
+pub fn hex_arg() -> Arg<'static, 'static> {
+    Arg::with_name("hex")
+        .long("hex")
+        .help("Key and value in hex format, same as --key-codec hex --value-codec hex")
+        .required(false)
+        .takes_value(false)
+}
+
+/// `--key-codec`, its `--key_hex` shorthand and `--key-format` for composite keys
+pub fn key_args() -> [Arg<'static, 'static>; 3] {
+    [
+        Arg::with_name("key_hex")
+            .long("key_hex")
+            .help("Key in hex format, same as --key-codec hex")
+            .required(false)
+            .takes_value(false),
+        Arg::with_name("key_codec")
+            .long("key-codec")
+            .help("Codec keys are typed and printed with")
+            .required(false)
+            .takes_value(true)
+            .possible_values(Codec::VARIANTS)
+            .conflicts_with("key_hex"),
+        Arg::with_name("key_format")
+            .long("key-format")
+            .help("Template of composite keys typed and printed as comma separated values, e.g. u8,u64be,str")
+            .required(false)
+            .takes_value(true)
+            .conflicts_with_all(&["key_hex", "key_codec"]),
+    ]
+}
+
+/// `--value-codec`, its `--value_hex` shorthand and schemas of serialized values
+pub fn value_args() -> [Arg<'static, 'static>; 5] {
+    [
+        Arg::with_name("value_hex")
+            .long("value_hex")
+            .help("Value in hex format, same as --value-codec hex")
+            .required(false)
+            .takes_value(false),
+        Arg::with_name("value_codec")
+            .long("value-codec")
+            .alias("value-decoder")
+            .help("Codec values are typed and printed with, structured values are shown as JSON")
+            .required(false)
+            .takes_value(true)
+            .possible_values(Codec::VARIANTS)
+            .conflicts_with("value_hex"),
+        Arg::with_name("proto_descriptor")
+            .long("proto-descriptor")
+            .help("Compiled protobuf descriptor set, values are typed and printed as JSON")
+            .required(false)
+            .takes_value(true)
+            .requires("proto_message")
+            .conflicts_with_all(&["value_hex", "value_codec"]),
+        Arg::with_name("proto_message")
+            .long("proto-message")
+            .help("Full name of the protobuf message of values, e.g. package.Type")
+            .required(false)
+            .takes_value(true)
+            .requires("proto_descriptor"),
+        Arg::with_name("bincode_schema")
+            .long("bincode-schema")
+            .help("Fields of bincode serialized values, e.g. id:u64,name:string")
+            .required(false)
+            .takes_value(true)
+            .conflicts_with_all(&["value_hex", "value_codec", "proto_descriptor"]),
+    ]
+}
+
+fn codec_of(matches: &ArgMatches, codec: &str, hex: &str) -> Result<Option<Codec>, Box<dyn Error>> {
+    match matches.value_of(codec) {
+        Some(name) => Ok(Some(name.parse()?)),
+        None if matches.is_present(hex) || matches.is_present("hex") => Ok(Some(Codec::Hex)),
+        None => Ok(None),
+    }
+}
+
+/// The key codec chosen in the subcommand matches, an explicit codec wins over `--hex`.
+///
+/// `None` if no key codec is given.
+pub fn key_codec(matches: &ArgMatches) -> Result<Option<Codec>, Box<dyn Error>> {
+    match matches.value_of("key_format") {
+        Some(format) => Ok(Some(Codec::Template(format.parse()?))),
+        None => codec_of(matches, "key_codec", "key_hex"),
+    }
+}
+
+/// The value codec chosen in the subcommand matches, an explicit codec wins over `--hex`.
+///
+/// `None` if no value codec is given.
+pub fn value_codec(matches: &ArgMatches) -> Result<Option<Codec>, Box<dyn Error>> {
+    match matches.value_of("proto_descriptor") {
+        Some(path) => Ok(Some(Codec::Protobuf(protobuf::message_descriptor(
+            path,
+            matches.value_of("proto_message").unwrap(),
+        )?))),
+        None => match matches.value_of("bincode_schema") {
+            Some(schema) => Ok(Some(Codec::Bincode(schema.parse()?))),
+            None => codec_of(matches, "value_codec", "value_hex"),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let cases = [
+            ("hello", "utf8"),
+            ("0x00ff10", "hex"),
+            ("AP8Q", "base64"),
+            ("_-8", "base64url"),
+            ("a\\x00\\n\\xff", "escaped"),
+            ("4294967295", "u32be"),
+            ("1", "u32le"),
+            ("18446744073709551615", "u64be"),
+            ("42", "u64le"),
+            ("-42", "i64be"),
+            ("-1", "i64le"),
+            ("300", "varint"),
+        ];
+        for (text, name) in &cases {
+            let codec: Codec = name.parse().unwrap();
+            assert_eq!(codec.name(), *name);
+            let data = codec.decode(text).unwrap();
+            assert_eq!(codec.encode(&data), *text, "codec {}", name);
+        }
+        assert!("utf16".parse::<Codec>().is_err());
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(Codec::U32Be.decode("1").unwrap(), [0, 0, 0, 1]);
+        assert_eq!(Codec::U64Le.decode("1").unwrap(), [1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(Codec::Varint.decode("300").unwrap(), [0xac, 0x02]);
+        assert_eq!(Codec::Escaped.decode("\\'\\t\\\\").unwrap(), b"'\t\\");
+        assert!(Codec::Escaped.decode("\\x4").is_err());
+        assert!(Codec::Escaped.decode("\\q").is_err());
+        assert!(Codec::U32Be.decode("4294967296").is_err());
+        assert!(Codec::Base64.decode("!").is_err());
+    }
+
+    #[test]
+    fn test_encode_fallback() {
+        assert_eq!(Codec::U32Be.encode(b"\x01\x02"), "0x0102");
+        assert_eq!(Codec::Varint.encode(b"\x80"), "0x80");
+        assert_eq!(Codec::Varint.encode(b"\x01\x02"), "0x0102");
+        assert_eq!(Codec::File.encode(b"\x01"), "0x01");
+    }
+
+    #[test]
+    fn test_decode_fallback() {
+        let json = Codec::Serialized(Serialization::Json);
+        for codec in [Codec::U32Be, Codec::Varint, json.clone()] {
+            let data = b"\x01\x02\xff";
+            assert_eq!(codec.decode(&codec.encode(data)).unwrap(), data);
+        }
+        assert_eq!(json.decode("\"0x01\"").unwrap(), b"\"0x01\"");
+        assert!(json.decode("0xzz").is_err());
+        assert_eq!(Codec::Utf8.decode("0x01").unwrap(), b"0x01");
+    }
+}
@@ -0,0 +1,182 @@
+use simple_error::SimpleError;
+use std::boxed::Box;
+use std::io::{self, Cursor, Read, Write};
+use std::str::FromStr;
+
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const LZ4_MAGIC: &[u8] = &[0x04, 0x22, 0x4d, 0x18];
+
+/// Compression of dump files selectable with `--compression`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    None,
+    Zstd,
+    Gzip,
+    Lz4,
+}
+
+impl Compression {
+    pub const VARIANTS: &'static [&'static str] = &["none", "zstd", "gzip", "lz4"];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            Compression::Zstd => "zstd",
+            Compression::Gzip => "gzip",
+            Compression::Lz4 => "lz4",
+        }
+    }
+
+    /// Extension of the files with this compression
+    pub fn extension(self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Zstd => Some("zst"),
+            Compression::Gzip => Some("gz"),
+            Compression::Lz4 => Some("lz4"),
+        }
+    }
+
+    /// Guesses the compression by the file extension
+    pub fn from_path(path: &str) -> Compression {
+        let extension = std::path::Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default();
+        match extension {
+            "zst" | "zstd" => Compression::Zstd,
+            "gz" | "gzip" => Compression::Gzip,
+            "lz4" => Compression::Lz4,
+            _ => Compression::None,
+        }
+    }
+
+    /// Recognizes the compression by the magic number the data starts with
+    pub fn detect(data: &[u8]) -> Compression {
+        if data.starts_with(ZSTD_MAGIC) {
+            Compression::Zstd
+        } else if data.starts_with(GZIP_MAGIC) {
+            Compression::Gzip
+        } else if data.starts_with(LZ4_MAGIC) {
+            Compression::Lz4
+        } else {
+            Compression::None
+        }
+    }
+
+    pub fn encoder<'w>(self, out: Box<dyn Write + 'w>) -> io::Result<Box<dyn Encoder + 'w>> {
+        Ok(match self {
+            Compression::None => Box::new(out),
+            Compression::Zstd => Box::new(zstd::Encoder::new(out, 0)?),
+            Compression::Gzip => Box::new(flate2::write::GzEncoder::new(
+                out,
+                flate2::Compression::default(),
+            )),
+            Compression::Lz4 => Box::new(lz4_flex::frame::FrameEncoder::new(out)),
+        })
+    }
+
+    pub fn decoder<'r>(self, input: Box<dyn Read + 'r>) -> io::Result<Box<dyn Read + 'r>> {
+        Ok(match self {
+            Compression::None => input,
+            Compression::Zstd => Box::new(zstd::Decoder::new(input)?),
+            // Concatenated gzip streams, e.g. produced by pigz, are read as a whole
+            Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(input)),
+            Compression::Lz4 => Box::new(lz4_flex::frame::FrameDecoder::new(input)),
+        })
+    }
+}
+
+impl FromStr for Compression {
+    type Err = SimpleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Compression::None),
+            "zstd" => Ok(Compression::Zstd),
+            "gzip" => Ok(Compression::Gzip),
+            "lz4" => Ok(Compression::Lz4),
+            _ => Err(SimpleError::new(format!("Unknown compression: {}", s))),
+        }
+    }
+}
+
+/// A compressing writer which has to be finished to write the end of the stream
+pub trait Encoder: Write {
+    /// Writes the end of the stream and flushes the underlying writer
+    fn finish(self: Box<Self>) -> io::Result<()>;
+}
+
+impl<'w> Encoder for Box<dyn Write + 'w> {
+    fn finish(mut self: Box<Self>) -> io::Result<()> {
+        self.flush()
+    }
+}
+
+impl<'w> Encoder for zstd::Encoder<'static, Box<dyn Write + 'w>> {
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        (*self).finish()?.flush()
+    }
+}
+
+impl<'w> Encoder for flate2::write::GzEncoder<Box<dyn Write + 'w>> {
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        (*self).finish()?.flush()
+    }
+}
+
+impl<'w> Encoder for lz4_flex::frame::FrameEncoder<Box<dyn Write + 'w>> {
+    fn finish(self: Box<Self>) -> io::Result<()> {
+        (*self).finish()?.flush()
+    }
+}
+
+/// Decompresses the input according to its magic number
+pub fn auto_decoder<'r>(mut input: Box<dyn Read + 'r>) -> io::Result<Box<dyn Read + 'r>> {
+    // Pipes may return less than asked, so read the magic number byte by byte
+    let mut magic = Vec::new();
+    while magic.len() < ZSTD_MAGIC.len() {
+        let mut byte = [0u8; 1];
+        if input.read(&mut byte)? == 0 {
+            break;
+        }
+        magic.push(byte[0]);
+    }
+    let compression = Compression::detect(&magic);
+    compression.decoder(Box::new(Cursor::new(magic).chain(input)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_path() {
+        assert_eq!(Compression::from_path("dump.zst"), Compression::Zstd);
+        assert_eq!(Compression::from_path("dump.txt.gz"), Compression::Gzip);
+        assert_eq!(Compression::from_path("/tmp/dump.lz4"), Compression::Lz4);
+        assert_eq!(Compression::from_path("dump.txt"), Compression::None);
+        assert_eq!(Compression::from_path("-"), Compression::None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data = b"1111 ==> 1111\n2222 ==> 2222\n".repeat(100);
+        for name in Compression::VARIANTS {
+            let compression: Compression = name.parse().unwrap();
+            let mut compressed = Vec::new();
+            let mut encoder = compression.encoder(Box::new(&mut compressed)).unwrap();
+            encoder.write_all(&data).unwrap();
+            encoder.finish().unwrap();
+            assert_eq!(Compression::detect(&compressed), compression);
+
+            let mut decompressed = Vec::new();
+            auto_decoder(Box::new(compressed.as_slice()))
+                .unwrap()
+                .read_to_end(&mut decompressed)
+                .unwrap();
+            assert_eq!(decompressed, data);
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use serde_json::{Map, Value};
+use simple_error::SimpleError;
+use std::boxed::Box;
+use std::convert::TryInto;
+use std::error::Error;
+use std::str::FromStr;
+
+const SEPARATOR: char = ',';
+
+/// A field of a composite key
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    U8,
+    U16Be,
+    U16Le,
+    U32Be,
+    U32Le,
+    U64Be,
+    U64Le,
+    I32Be,
+    I32Le,
+    I64Be,
+    I64Le,
+    /// UTF-8 string taking the rest of the key
+    Str,
+    /// Bytes taking the rest of the key, in hex
+    Hex,
+}
+
+impl Field {
+    const VARIANTS: &'static [&'static str] = &[
+        "u8", "u16be", "u16le", "u32be", "u32le", "u64be", "u64le", "i32be", "i32le", "i64be",
+        "i64le", "str", "hex",
+    ];
+
+    fn name(self) -> &'static str {
+        Field::VARIANTS[self as usize]
+    }
+
+    /// Size of fixed width fields
+    fn size(self) -> Option<usize> {
+        match self {
+            Field::U8 => Some(1),
+            Field::U16Be | Field::U16Le => Some(2),
+            Field::U32Be | Field::U32Le | Field::I32Be | Field::I32Le => Some(4),
+            Field::U64Be | Field::U64Le | Field::I64Be | Field::I64Le => Some(8),
+            Field::Str | Field::Hex => None,
+        }
+    }
+
+    fn parse(self, text: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(match self {
+            Field::U8 => vec![text.parse::<u8>()?],
+            Field::U16Be => text.parse::<u16>()?.to_be_bytes().to_vec(),
+            Field::U16Le => text.parse::<u16>()?.to_le_bytes().to_vec(),
+            Field::U32Be => text.parse::<u32>()?.to_be_bytes().to_vec(),
+            Field::U32Le => text.parse::<u32>()?.to_le_bytes().to_vec(),
+            Field::U64Be => text.parse::<u64>()?.to_be_bytes().to_vec(),
+            Field::U64Le => text.parse::<u64>()?.to_le_bytes().to_vec(),
+            Field::I32Be => text.parse::<i32>()?.to_be_bytes().to_vec(),
+            Field::I32Le => text.parse::<i32>()?.to_le_bytes().to_vec(),
+            Field::I64Be => text.parse::<i64>()?.to_be_bytes().to_vec(),
+            Field::I64Le => text.parse::<i64>()?.to_le_bytes().to_vec(),
+            Field::Str => text.as_bytes().to_vec(),
+            Field::Hex => crate::utils::hex::decode(text)?,
+        })
+    }
+
+    /// Formats the field, the data has exactly the size of fixed width fields
+    fn format(self, data: &[u8]) -> Option<String> {
+        Some(match self {
+            Field::U8 => data[0].to_string(),
+            Field::U16Be => u16::from_be_bytes(data.try_into().ok()?).to_string(),
+            Field::U16Le => u16::from_le_bytes(data.try_into().ok()?).to_string(),
+            Field::U32Be => u32::from_be_bytes(data.try_into().ok()?).to_string(),
+            Field::U32Le => u32::from_le_bytes(data.try_into().ok()?).to_string(),
+            Field::U64Be => u64::from_be_bytes(data.try_into().ok()?).to_string(),
+            Field::U64Le => u64::from_le_bytes(data.try_into().ok()?).to_string(),
+            Field::I32Be => i32::from_be_bytes(data.try_into().ok()?).to_string(),
+            Field::I32Le => i32::from_le_bytes(data.try_into().ok()?).to_string(),
+            Field::I64Be => i64::from_be_bytes(data.try_into().ok()?).to_string(),
+            Field::I64Le => i64::from_le_bytes(data.try_into().ok()?).to_string(),
+            Field::Str => String::from_utf8(data.to_vec()).ok()?,
+            Field::Hex => crate::utils::hex::encode(data),
+        })
+    }
+
+    /// The field as a JSON number or string
+    fn to_json(self, data: &[u8]) -> Option<Value> {
+        let text = self.format(data)?;
+        match self {
+            Field::Str | Field::Hex => Some(Value::String(text)),
+            _ => serde_json::from_str(&text).ok(),
+        }
+    }
+}
+
+impl FromStr for Field {
+    type Err = SimpleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "u8" => Ok(Field::U8),
+            "u16be" => Ok(Field::U16Be),
+            "u16le" => Ok(Field::U16Le),
+            "u32be" => Ok(Field::U32Be),
+            "u32le" => Ok(Field::U32Le),
+            "u64be" => Ok(Field::U64Be),
+            "u64le" => Ok(Field::U64Le),
+            "i32be" => Ok(Field::I32Be),
+            "i32le" => Ok(Field::I32Le),
+            "i64be" => Ok(Field::I64Be),
+            "i64le" => Ok(Field::I64Le),
+            "str" => Ok(Field::Str),
+            "hex" => Ok(Field::Hex),
+            _ => Err(SimpleError::new(format!(
+                "Unknown key field: {}, expected one of {}",
+                s,
+                Field::VARIANTS.join(", ")
+            ))),
+        }
+    }
+}
+
+/// Template of composite keys given with `--key-format`, e.g. `u8,u64be,str`.
+///
+/// Keys are typed and printed as comma separated values of the fields. Typed keys may have
+/// fewer values than fields, which makes a key prefix handy for `scan --from/--to`.
+/// Fields may be named, e.g. `tenant:u8,user_id:u64be`, to be referred to in queries.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyFormat {
+    fields: Vec<Field>,
+    names: Vec<Option<String>>,
+}
+
+impl KeyFormat {
+    /// Turns comma separated values into a key, the last string field may contain commas
+    pub fn parse(&self, text: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let mut key = Vec::new();
+        for (field, value) in self
+            .fields
+            .iter()
+            .zip(text.splitn(self.fields.len(), SEPARATOR))
+        {
+            match field.parse(value) {
+                Ok(bytes) => key.extend(bytes),
+                Err(error) => {
+                    return Err(Box::new(SimpleError::new(format!(
+                        "Invalid value '{}' of {} key field: {}",
+                        value,
+                        field.name(),
+                        error
+                    ))))
+                }
+            }
+        }
+        Ok(key)
+    }
+
+    /// Splits the key into the data of the fields, `None` if the key does not match the format
+    fn split<'k>(&self, key: &'k [u8]) -> Option<Vec<&'k [u8]>> {
+        let mut values = Vec::with_capacity(self.fields.len());
+        let mut rest = key;
+        for field in &self.fields {
+            let size = field.size().unwrap_or(rest.len());
+            if rest.len() < size {
+                return None;
+            }
+            let (data, tail) = rest.split_at(size);
+            values.push(data);
+            rest = tail;
+        }
+        if rest.is_empty() {
+            Some(values)
+        } else {
+            None
+        }
+    }
+
+    /// Formats the key as comma separated values, `None` if the key does not match the format
+    pub fn format(&self, key: &[u8]) -> Option<String> {
+        let values = self
+            .fields
+            .iter()
+            .zip(self.split(key)?)
+            .map(|(field, data)| field.format(data))
+            .collect::<Option<Vec<_>>>()?;
+        Some(values.join(&SEPARATOR.to_string()))
+    }
+
+    /// The key as an object of named fields, unnamed ones are named by their index, or as an
+    /// array if no field is named
+    pub fn to_json(&self, key: &[u8]) -> Option<Value> {
+        let values = self
+            .fields
+            .iter()
+            .zip(self.split(key)?)
+            .map(|(field, data)| field.to_json(data))
+            .collect::<Option<Vec<_>>>()?;
+        if self.names.iter().all(Option::is_none) {
+            return Some(Value::Array(values));
+        }
+        let mut object = Map::new();
+        for (i, (name, value)) in self.names.iter().zip(values).enumerate() {
+            object.insert(name.clone().unwrap_or_else(|| i.to_string()), value);
+        }
+        Some(Value::Object(object))
+    }
+}
+
+impl FromStr for KeyFormat {
+    type Err = SimpleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = Vec::new();
+        let mut names = Vec::new();
+        for field in s.split(SEPARATOR) {
+            let (name, field) = match field.split_once(':') {
+                Some((name, field)) => (Some(name.trim().to_string()), field),
+                None => (None, field),
+            };
+            fields.push(field.trim().parse::<Field>()?);
+            names.push(name);
+        }
+        if let Some(position) = fields.iter().position(|field| field.size().is_none()) {
+            if position + 1 != fields.len() {
+                return Err(SimpleError::new(format!(
+                    "Variable length key field {} has to be the last one",
+                    fields[position].name()
+                )));
+            }
+        }
+        Ok(KeyFormat { fields, names })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format() {
+        let format: KeyFormat = "u8, u64be, str".parse().unwrap();
+        let key = format.parse("3,42,alice, bob").unwrap();
+        assert_eq!(key, b"\x03\x00\x00\x00\x00\x00\x00\x00\x2aalice, bob");
+        assert_eq!(format.format(&key).unwrap(), "3,42,alice, bob");
+        // A prefix of the fields
+        assert_eq!(format.parse("3").unwrap(), b"\x03");
+        assert_eq!(format.format(b"\x03"), None);
+        assert_eq!(
+            format.format(b"\x03\x00\x00\x00\x00\x00\x00\x00\x2a\xff"),
+            None
+        );
+
+        let format: KeyFormat = "i32le,u16be".parse().unwrap();
+        assert_eq!(format.parse("-2,258").unwrap(), b"\xfe\xff\xff\xff\x01\x02");
+        assert_eq!(
+            format.format(b"\xfe\xff\xff\xff\x01\x02").unwrap(),
+            "-2,258"
+        );
+        assert_eq!(format.format(b"\xfe\xff\xff\xff\x01\x02\x03"), None);
+        assert!(format.parse("1,65536").is_err());
+
+        let format: KeyFormat = "u8,hex".parse().unwrap();
+        assert_eq!(format.format(b"\x01\xab").unwrap(), "1,0xab");
+    }
+
+    #[test]
+    fn test_to_json() {
+        let format: KeyFormat = "u8,i32be,str".parse().unwrap();
+        assert_eq!(
+            format.to_json(b"\x01\xff\xff\xff\xfeab").unwrap(),
+            serde_json::json!([1, -2, "ab"])
+        );
+        let format: KeyFormat = "tenant:u8, u16be, name : str".parse().unwrap();
+        assert_eq!(format.parse("1,2,bob").unwrap(), b"\x01\x00\x02bob");
+        assert_eq!(
+            format.to_json(b"\x01\x00\x02bob").unwrap(),
+            serde_json::json!({"tenant": 1, "1": 2, "name": "bob"})
+        );
+        assert_eq!(format.to_json(b"\x01"), None);
+    }
+
+    #[test]
+    fn test_invalid_format() {
+        assert!("u8,float".parse::<KeyFormat>().is_err());
+        assert!("str,u8".parse::<KeyFormat>().is_err());
+    }
+}
@@ -1 +1,6 @@
+pub mod codec;
+pub mod compression;
 pub mod hex;
+pub mod key_format;
+pub mod protobuf;
+pub mod serialized;
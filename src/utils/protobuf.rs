@@ -0,0 +1,53 @@
+use prost::Message;
+use prost_reflect::{DescriptorPool, DynamicMessage, MessageDescriptor};
+use simple_error::SimpleError;
+use std::boxed::Box;
+use std::error::Error;
+
+/// Looks the message type up in a compiled descriptor set, e.g. made by
+/// `protoc --include_imports --descriptor_set_out=FILE.desc`
+pub fn message_descriptor(path: &str, message: &str) -> Result<MessageDescriptor, Box<dyn Error>> {
+    let pool = DescriptorPool::decode(std::fs::read(path)?.as_slice())?;
+    match pool.get_message_by_name(message) {
+        Some(descriptor) => Ok(descriptor),
+        None => Err(Box::new(SimpleError::new(format!(
+            "Message {} is not found in {}",
+            message, path
+        )))),
+    }
+}
+
+/// Decodes a message into JSON, `None` if the data is not a message of the type
+pub fn to_json(descriptor: &MessageDescriptor, data: &[u8]) -> Option<String> {
+    let message = DynamicMessage::decode(descriptor.clone(), data).ok()?;
+    serde_json::to_string(&message).ok()
+}
+
+/// Encodes a message given in JSON
+pub fn from_json(descriptor: &MessageDescriptor, json: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let message = DynamicMessage::deserialize(descriptor.clone(), &mut deserializer)?;
+    deserializer.end()?;
+    Ok(message.encode_to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/user.desc");
+        assert!(message_descriptor(path, "test.Missing").is_err());
+
+        let descriptor = message_descriptor(path, "test.User").unwrap();
+        let data = from_json(&descriptor, r#"{"name": "alice", "id": "42"}"#).unwrap();
+        assert_eq!(data, b"\x0a\x05alice\x10\x2a");
+        assert_eq!(
+            to_json(&descriptor, &data).unwrap(),
+            r#"{"name":"alice","id":"42"}"#
+        );
+        assert_eq!(to_json(&descriptor, b"\xff\xff"), None);
+        assert!(from_json(&descriptor, r#"{"age": 1}"#).is_err());
+    }
+}
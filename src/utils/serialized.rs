@@ -0,0 +1,274 @@
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use simple_error::SimpleError;
+use std::boxed::Box;
+use std::convert::{TryFrom, TryInto};
+use std::error::Error;
+use std::str::FromStr;
+
+/// Structured values shown and typed as JSON documents
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Serialization {
+    Json,
+    MsgPack,
+    Cbor,
+}
+
+impl Serialization {
+    /// Stores the JSON document in the serialization
+    pub fn serialize(self, json: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let value: Value = serde_json::from_str(json)?;
+        Ok(match self {
+            Serialization::Json => serde_json::to_vec(&value)?,
+            Serialization::MsgPack => rmp_serde::to_vec(&value)?,
+            Serialization::Cbor => serde_cbor::to_vec(&value)?,
+        })
+    }
+
+    /// Decodes the whole data into JSON, `None` if it is not a single value of the serialization
+    pub fn to_json(self, data: &[u8]) -> Option<String> {
+        let value: Value = match self {
+            Serialization::Json => serde_json::from_slice(data).ok()?,
+            Serialization::MsgPack => {
+                let mut rest = data;
+                let value =
+                    Value::deserialize(&mut rmp_serde::Deserializer::new(&mut rest)).ok()?;
+                if !rest.is_empty() {
+                    return None;
+                }
+                value
+            }
+            Serialization::Cbor => serde_cbor::from_slice(data).ok()?,
+        };
+        serde_json::to_string(&value).ok()
+    }
+}
+
+/// Type of a bincode field
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FieldType {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    F32,
+    F64,
+    String,
+    /// Byte vector shown in hex
+    Bytes,
+}
+
+impl FromStr for FieldType {
+    type Err = SimpleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bool" => Ok(FieldType::Bool),
+            "u8" => Ok(FieldType::U8),
+            "u16" => Ok(FieldType::U16),
+            "u32" => Ok(FieldType::U32),
+            "u64" => Ok(FieldType::U64),
+            "i8" => Ok(FieldType::I8),
+            "i16" => Ok(FieldType::I16),
+            "i32" => Ok(FieldType::I32),
+            "i64" => Ok(FieldType::I64),
+            "f32" => Ok(FieldType::F32),
+            "f64" => Ok(FieldType::F64),
+            "string" => Ok(FieldType::String),
+            "bytes" => Ok(FieldType::Bytes),
+            _ => Err(SimpleError::new(format!("Unknown bincode type: {}", s))),
+        }
+    }
+}
+
+/// Takes `N` bytes from the front of the data
+fn take<const N: usize>(data: &mut &[u8]) -> Option<[u8; N]> {
+    if data.len() < N {
+        return None;
+    }
+    let (head, tail) = data.split_at(N);
+    *data = tail;
+    head.try_into().ok()
+}
+
+impl FieldType {
+    fn read(self, data: &mut &[u8]) -> Option<Value> {
+        Some(match self {
+            FieldType::Bool => match take::<1>(data)? {
+                [0] => Value::Bool(false),
+                [1] => Value::Bool(true),
+                _ => return None,
+            },
+            FieldType::U8 => u8::from_le_bytes(take(data)?).into(),
+            FieldType::U16 => u16::from_le_bytes(take(data)?).into(),
+            FieldType::U32 => u32::from_le_bytes(take(data)?).into(),
+            FieldType::U64 => u64::from_le_bytes(take(data)?).into(),
+            FieldType::I8 => i8::from_le_bytes(take(data)?).into(),
+            FieldType::I16 => i16::from_le_bytes(take(data)?).into(),
+            FieldType::I32 => i32::from_le_bytes(take(data)?).into(),
+            FieldType::I64 => i64::from_le_bytes(take(data)?).into(),
+            FieldType::F32 => f64::from(f32::from_le_bytes(take(data)?)).into(),
+            FieldType::F64 => f64::from_le_bytes(take(data)?).into(),
+            FieldType::String | FieldType::Bytes => {
+                let length = u64::from_le_bytes(take(data)?).try_into().ok()?;
+                if data.len() < length {
+                    return None;
+                }
+                let (bytes, tail) = data.split_at(length);
+                *data = tail;
+                if self == FieldType::String {
+                    Value::String(String::from_utf8(bytes.to_vec()).ok()?)
+                } else {
+                    Value::String(crate::utils::hex::encode(bytes))
+                }
+            }
+        })
+    }
+
+    fn write(self, value: &Value, out: &mut Vec<u8>) -> Option<()> {
+        let unsigned = || value.as_u64();
+        let signed = || value.as_i64();
+        match self {
+            FieldType::Bool => out.push(value.as_bool()? as u8),
+            FieldType::U8 => out.push(unsigned()?.try_into().ok()?),
+            FieldType::U16 => out.extend(u16::try_from(unsigned()?).ok()?.to_le_bytes()),
+            FieldType::U32 => out.extend(u32::try_from(unsigned()?).ok()?.to_le_bytes()),
+            FieldType::U64 => out.extend(unsigned()?.to_le_bytes()),
+            FieldType::I8 => out.extend(i8::try_from(signed()?).ok()?.to_le_bytes()),
+            FieldType::I16 => out.extend(i16::try_from(signed()?).ok()?.to_le_bytes()),
+            FieldType::I32 => out.extend(i32::try_from(signed()?).ok()?.to_le_bytes()),
+            FieldType::I64 => out.extend(signed()?.to_le_bytes()),
+            FieldType::F32 => out.extend((value.as_f64()? as f32).to_le_bytes()),
+            FieldType::F64 => out.extend(value.as_f64()?.to_le_bytes()),
+            FieldType::String | FieldType::Bytes => {
+                let text = value.as_str()?;
+                let bytes = if self == FieldType::String {
+                    text.as_bytes().to_vec()
+                } else {
+                    crate::utils::hex::decode(text).ok()?
+                };
+                out.extend((bytes.len() as u64).to_le_bytes());
+                out.extend(bytes);
+            }
+        }
+        Some(())
+    }
+}
+
+/// Layout of a struct serialized with the default bincode options, e.g. `id:u64,name:string`.
+///
+/// Bincode is not self-describing, so the fields have to be listed in the order of the struct.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BincodeSchema {
+    fields: Vec<(String, FieldType)>,
+}
+
+impl BincodeSchema {
+    /// Encodes a JSON object having all the fields of the schema
+    pub fn serialize(&self, json: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let value: Value = serde_json::from_str(json)?;
+        let object = match value.as_object() {
+            Some(object) => object,
+            None => return Err(Box::new(SimpleError::new("Expected a JSON object"))),
+        };
+        let mut data = Vec::new();
+        for (name, field_type) in &self.fields {
+            let written = object
+                .get(name)
+                .and_then(|value| field_type.write(value, &mut data));
+            if written.is_none() {
+                return Err(Box::new(SimpleError::new(format!(
+                    "Field {} is missing or is not a valid {:?}",
+                    name, field_type
+                ))));
+            }
+        }
+        Ok(data)
+    }
+
+    /// Decodes the whole data into a JSON object, `None` if it does not match the schema
+    pub fn to_json(&self, mut data: &[u8]) -> Option<String> {
+        let mut object = Map::new();
+        for (name, field_type) in &self.fields {
+            object.insert(name.clone(), field_type.read(&mut data)?);
+        }
+        if !data.is_empty() {
+            return None;
+        }
+        serde_json::to_string(&object).ok()
+    }
+}
+
+impl FromStr for BincodeSchema {
+    type Err = SimpleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields = s
+            .split(',')
+            .map(|field| match field.trim().split_once(':') {
+                Some((name, field_type)) => {
+                    Ok((name.trim().to_string(), field_type.trim().parse()?))
+                }
+                None => Err(SimpleError::new(format!(
+                    "Expected name:type bincode field, got {}",
+                    field
+                ))),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(BincodeSchema { fields })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializations() {
+        let json = r#"{"id":42,"name":"alice","tags":["a","b"]}"#;
+        for serialization in &[
+            Serialization::Json,
+            Serialization::MsgPack,
+            Serialization::Cbor,
+        ] {
+            let data = serialization.serialize(json).unwrap();
+            assert_eq!(serialization.to_json(&data).unwrap(), json);
+            assert!(serialization.serialize("{").is_err());
+        }
+        assert_eq!(
+            Serialization::MsgPack.serialize("[1]").unwrap(),
+            b"\x91\x01"
+        );
+        assert_eq!(Serialization::MsgPack.to_json(b"\x91\x01\x02"), None);
+        assert_eq!(Serialization::Cbor.to_json(b"\xff"), None);
+        assert_eq!(Serialization::Json.to_json(b"hello"), None);
+    }
+
+    #[test]
+    fn test_bincode_schema() {
+        let schema: BincodeSchema =
+            "id:u64, name:string, active:bool, score:f32, raw:bytes, delta:i16"
+                .parse()
+                .unwrap();
+        let json = r#"{"active":true,"delta":-2,"id":42,"name":"bob","raw":"0x00ff","score":1.5}"#;
+        let data = schema.serialize(json).unwrap();
+        let mut expected = Vec::new();
+        expected.extend(b"\x2a\x00\x00\x00\x00\x00\x00\x00");
+        expected.extend(b"\x03\x00\x00\x00\x00\x00\x00\x00bob");
+        expected.extend(b"\x01");
+        expected.extend(1.5f32.to_le_bytes());
+        expected.extend(b"\x02\x00\x00\x00\x00\x00\x00\x00\x00\xff");
+        expected.extend(b"\xfe\xff");
+        assert_eq!(data, expected);
+        assert_eq!(schema.to_json(&data).unwrap(), json);
+        assert_eq!(schema.to_json(&data[..data.len() - 1]), None);
+        assert!(schema.serialize(r#"{"id":-1}"#).is_err());
+        assert!("id".parse::<BincodeSchema>().is_err());
+        assert!("id:u128".parse::<BincodeSchema>().is_err());
+    }
+}
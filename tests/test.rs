@@ -676,3 +676,1843 @@ fn dump() -> Result<(), Box<dyn std::error::Error>> {
     );
     Ok(())
 }
+
+#[test]
+fn scan_output_formats() -> Result<(), Box<dyn std::error::Error>> {
+    let kv = ["1111", "1111", "2222", "2222"];
+    let path = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("batchput")
+        .args(&kv);
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("--output-format")
+        .arg("jsonl")
+        .arg("scan");
+    cmd.assert()
+        .success()
+        .stdout("{\"key\":\"1111\",\"value\":\"1111\"}\n{\"key\":\"2222\",\"value\":\"2222\"}\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("--output-format")
+        .arg("csv")
+        .arg("scan")
+        .arg("--value_hex");
+    cmd.assert()
+        .success()
+        .stdout("key,value\n1111,0x31313131\n2222,0x32323232\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("--output-format")
+        .arg("json")
+        .arg("get")
+        .arg("1111");
+    cmd.assert()
+        .success()
+        .stdout("[\n  {\"key\":\"1111\",\"value\":\"1111\"}\n]\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("--output-format")
+        .arg("yaml")
+        .arg("scan");
+    cmd.assert().failure();
+    Ok(())
+}
+
+#[test]
+fn read_only_and_secondary() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let secondary_path = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("put")
+        .arg("hello")
+        .arg("world");
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("--read-only")
+        .arg("get")
+        .arg("hello");
+    cmd.assert().success().stdout("world\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("--secondary")
+        .arg(secondary_path.path())
+        .arg("scan");
+    cmd.assert().success().stdout("hello : world\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("--read-only")
+        .arg("put")
+        .arg("hello")
+        .arg("there");
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stderr("Failed: put is not allowed in read-only mode\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("--secondary")
+        .arg(secondary_path.path())
+        .arg("delete")
+        .arg("hello");
+    cmd.assert()
+        .failure()
+        .code(1)
+        .stderr("Failed: delete is not allowed in secondary mode\n");
+    Ok(())
+}
+
+#[test]
+fn multiple_column_families() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("--column_family")
+        .arg("cf1")
+        .arg("put")
+        .arg("1111")
+        .arg("aaaa");
+    cmd.assert().success().stdout("OK\n");
+
+    // Other column families are opened automatically
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("put")
+        .arg("2222")
+        .arg("bbbb");
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("--column_family")
+        .arg("cf1")
+        .arg("get")
+        .arg("1111");
+    cmd.assert().success().stdout("aaaa\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db").arg(path.path()).arg("get").arg("1111");
+    cmd.assert().success().stderr("Not Found\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--all-column-families");
+    cmd.assert()
+        .success()
+        .stdout("default : 2222 : bbbb\ncf1 : 1111 : aaaa\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("--column_family")
+        .arg("cf1")
+        .arg("--column_family")
+        .arg("default")
+        .arg("get")
+        .arg("1111");
+    cmd.assert()
+        .failure()
+        .stderr("Failed: get operates on a single column family\n");
+    Ok(())
+}
+
+#[test]
+fn column_family_management() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("put")
+        .arg("hello")
+        .arg("world");
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("createcf")
+        .arg("--compression")
+        .arg("none")
+        .arg("--prefix-length")
+        .arg("4")
+        .arg("cf1");
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db").arg(path.path()).arg("listcf");
+    cmd.assert().success().stdout("default\ncf1\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("cfstats")
+        .arg("--all-column-families");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("default : estimated keys : "))
+        .stdout(predicate::str::contains("cf1 : estimated keys : "));
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("dropcf")
+        .arg("cf1")
+        .write_stdin("n\n");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::ends_with("Aborted\n"));
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("dropcf")
+        .arg("cf1")
+        .write_stdin("y\n");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::ends_with("OK\n"));
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db").arg(path.path()).arg("listcf");
+    cmd.assert().success().stdout("default\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("dropcf")
+        .arg("--yes")
+        .arg("default");
+    cmd.assert()
+        .failure()
+        .stderr("Failed: Default column family cannot be dropped\n");
+    Ok(())
+}
+
+#[test]
+fn shell() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let home = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.env("HOME", home.path())
+        .arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("shell")
+        .write_stdin(
+            "put hello world\nput \"two words\" value\nget hello\nunknown\nscan\ndelete hello\nget hello\nexit\nget \"two words\"\n",
+        );
+    cmd.assert()
+        .success()
+        .stdout("OK\nOK\nworld\ntwo words : value\nhello : world\nOK\n")
+        .stderr(predicate::str::contains("Failed: "))
+        .stderr(predicate::str::contains("Not Found"));
+    assert!(home.path().join(".rdbrowser_history").exists());
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.env("HOME", home.path())
+        .arg("--read-only")
+        .arg("--db")
+        .arg(path.path())
+        .arg("shell")
+        .write_stdin("put a b\nget \"two words\"\n");
+    cmd.assert()
+        .success()
+        .stdout("value\n")
+        .stderr(predicate::str::contains(
+            "put is not allowed in read-only mode",
+        ));
+    Ok(())
+}
+
+#[test]
+fn tui_wrong_page_size() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("tui")
+        .arg("--page-size")
+        .arg("0");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Page size must be positive"));
+    Ok(())
+}
+
+#[test]
+fn dump_and_load() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let kv = [
+        "1111",
+        "one",
+        "2222",
+        "two ==> 2",
+        "3333",
+        "",
+        "4444",
+        "four",
+    ];
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("batchput")
+        .args(&kv);
+    cmd.assert().success().stdout("OK\n");
+
+    let dump_dir = tempdir()?;
+    for (variant, hex) in [("plain", None), ("hex", Some("--hex"))] {
+        let dump_path = dump_dir.path().join(variant);
+        let mut cmd = Command::cargo_bin("rdbrowser")?;
+        cmd.arg("--db")
+            .arg(path.path())
+            .arg("dump")
+            .args(hex)
+            .arg("--output")
+            .arg(&dump_path);
+        cmd.assert().success();
+
+        let restored = tempdir()?;
+        let mut cmd = Command::cargo_bin("rdbrowser")?;
+        cmd.arg("--create_if_missing")
+            .arg("--db")
+            .arg(restored.path())
+            .arg("load")
+            .args(hex)
+            .arg("--batch-size")
+            .arg("3")
+            .arg("--input")
+            .arg(&dump_path);
+        cmd.assert()
+            .success()
+            .stdout("OK\n")
+            .stderr("Loaded 3 keys\nLoaded 4 keys\n");
+
+        let mut cmd = Command::cargo_bin("rdbrowser")?;
+        cmd.arg("--db").arg(restored.path()).arg("scan");
+        cmd.assert()
+            .success()
+            .stdout("1111 : one\n2222 : two ==> 2\n3333 : \n4444 : four\n");
+    }
+
+    let restored = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(restored.path())
+        .arg("--column_family")
+        .arg("cf1")
+        .arg("--column_family")
+        .arg("cf2")
+        .arg("load")
+        .arg("--with-column-family")
+        .arg("--input")
+        .arg("-")
+        .write_stdin("cf1 ==> a ==> 1\ncf2 ==> b ==> 2\n");
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(restored.path())
+        .arg("scan")
+        .arg("--all-column-families");
+    cmd.assert().success().stdout("cf1 : a : 1\ncf2 : b : 2\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(restored.path())
+        .arg("load")
+        .arg("--input")
+        .arg("-")
+        .write_stdin("a ==> 1\nmalformed\n");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Malformed line 2"));
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(restored.path())
+        .arg("load")
+        .arg("--input")
+        .arg("-")
+        .write_stdin("a ==> 1\nb ==> \u{fffd}\n");
+    cmd.assert().success().stderr(predicate::str::starts_with(
+        "Warning: line 2 contains U+FFFD",
+    ));
+    Ok(())
+}
+
+#[test]
+fn binary_dump_and_load() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    // Keys with the text dump delimiter and a newline, values with invalid UTF-8
+    let kv = ["0x6b203d3d3e20", "0x00ff", "0x6b0a", "0xfffe", "0x6b", "0x"];
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("batchput")
+        .arg("--hex")
+        .args(&kv);
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db").arg(path.path()).arg("createcf").arg("cf1");
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("--column_family")
+        .arg("cf1")
+        .arg("put")
+        .arg("a")
+        .arg("b");
+    cmd.assert().success().stdout("OK\n");
+
+    let dump_dir = tempdir()?;
+    let dump_path = dump_dir.path().join("dump.bin");
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("dump")
+        .arg("--all-column-families")
+        .arg("--format")
+        .arg("binary")
+        .arg("--output")
+        .arg(&dump_path);
+    cmd.assert().success();
+
+    let restored = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(restored.path())
+        .arg("load")
+        .arg("--format")
+        .arg("binary")
+        .arg("--input")
+        .arg(&dump_path);
+    cmd.assert()
+        .success()
+        .stdout("OK\n")
+        .stderr(predicate::str::ends_with("Loaded 4 keys\n"));
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(restored.path())
+        .arg("scan")
+        .arg("--all-column-families")
+        .arg("--hex");
+    cmd.assert().success().stdout(
+        "default : 0x6b : 0x\ndefault : 0x6b0a : 0xfffe\ndefault : 0x6b203d3d3e20 : 0x00ff\ncf1 : 0x61 : 0x62\n",
+    );
+
+    // Plain text would replace the invalid UTF-8
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("dump")
+        .arg("--output")
+        .arg(dump_dir.path().join("dump.txt"));
+    cmd.assert().failure().stderr(
+        "Failed: Value of key 0x6b0a is not valid UTF-8, dump it with --hex or --format binary\n",
+    );
+
+    let mut data = std::fs::read(&dump_path)?;
+    let last = data.len() - 1;
+    data[last] ^= 1;
+    std::fs::write(&dump_path, data)?;
+    let restored = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(restored.path())
+        .arg("load")
+        .arg("--format")
+        .arg("binary")
+        .arg("--input")
+        .arg(&dump_path);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Corrupted dump: checksum mismatch",
+    ));
+    Ok(())
+}
+
+#[test]
+fn compressed_and_streaming_dump() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let kv = ["1111", "1111", "2222", "2222", "3333", "3333"];
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("batchput")
+        .args(&kv);
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("dump")
+        .arg("--output")
+        .arg("-");
+    cmd.assert()
+        .success()
+        .stdout("1111 ==> 1111\n2222 ==> 2222\n3333 ==> 3333\n");
+
+    let dump_dir = tempdir()?;
+    for (name, format) in [
+        ("dump.zst", "text"),
+        ("dump.gz", "text"),
+        ("dump.lz4", "text"),
+        ("dump.bin.zst", "binary"),
+    ] {
+        let dump_path = dump_dir.path().join(name);
+        let mut cmd = Command::cargo_bin("rdbrowser")?;
+        cmd.arg("--db")
+            .arg(path.path())
+            .arg("dump")
+            .arg("--format")
+            .arg(format)
+            .arg("--output")
+            .arg(&dump_path);
+        cmd.assert().success();
+        assert!(!std::fs::read(&dump_path)?.starts_with(b"1111"));
+
+        let restored = tempdir()?;
+        let mut cmd = Command::cargo_bin("rdbrowser")?;
+        cmd.arg("--create_if_missing")
+            .arg("--db")
+            .arg(restored.path())
+            .arg("load")
+            .arg("--format")
+            .arg(format)
+            .arg("--input")
+            .arg(&dump_path);
+        cmd.assert().success().stdout("OK\n");
+
+        let mut cmd = Command::cargo_bin("rdbrowser")?;
+        cmd.arg("--db").arg(restored.path()).arg("scan");
+        cmd.assert()
+            .success()
+            .stdout("1111 : 1111\n2222 : 2222\n3333 : 3333\n");
+    }
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("dump")
+        .arg("--compression")
+        .arg("gzip")
+        .arg("--output")
+        .arg("-");
+    let compressed = cmd.assert().success().get_output().stdout.clone();
+
+    let restored = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(restored.path())
+        .arg("load")
+        .arg("--input")
+        .arg("-")
+        .write_stdin(compressed);
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db").arg(restored.path()).arg("scan");
+    cmd.assert()
+        .success()
+        .stdout("1111 : 1111\n2222 : 2222\n3333 : 3333\n");
+    Ok(())
+}
+
+#[test]
+fn parallel_dump() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let kv = ["1111", "1111", "2222", "2222", "3333", "3333"];
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("batchput")
+        .args(&kv);
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("dump")
+        .arg("--parallel")
+        .arg("2")
+        .arg("--output")
+        .arg("-");
+    cmd.assert()
+        .failure()
+        .stderr("Failed: Parallel dump requires an output directory\n");
+
+    let dump_dir = tempdir()?;
+    let output = dump_dir.path().join("dump");
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("dump")
+        .arg("--parallel")
+        .arg("2")
+        .arg("--output")
+        .arg(&output);
+    cmd.assert().success();
+
+    let manifest: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(output.join("manifest.json"))?)?;
+    let shards = manifest["shards"].as_array().unwrap();
+    let keys: u64 = shards
+        .iter()
+        .map(|shard| shard["keys"].as_u64().unwrap())
+        .sum();
+    assert_eq!(keys, 3);
+
+    let restored = tempdir()?;
+    for shard in shards {
+        let mut cmd = Command::cargo_bin("rdbrowser")?;
+        cmd.arg("--create_if_missing")
+            .arg("--db")
+            .arg(restored.path())
+            .arg("load")
+            .arg("--input")
+            .arg(output.join(shard["file"].as_str().unwrap()));
+        cmd.assert().success().stdout("OK\n");
+    }
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db").arg(restored.path()).arg("scan");
+    cmd.assert()
+        .success()
+        .stdout("1111 : 1111\n2222 : 2222\n3333 : 3333\n");
+    Ok(())
+}
+
+#[test]
+fn binary_safe_get() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let key = "hello";
+    let value = "00ff6869";
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("put")
+        .arg("--value_hex")
+        .arg(key)
+        .arg(value);
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db").arg(path.path()).arg("get").arg(key);
+    cmd.assert().success().stdout("0x00ff6869\n");
+
+    for (encoding, expected) in [
+        ("base64", "AP9oaQ==\n"),
+        ("escaped", "\\x00\\xffhi\n"),
+        ("lossy", "\u{0}\u{fffd}hi\n"),
+    ] {
+        let mut cmd = Command::cargo_bin("rdbrowser")?;
+        cmd.arg("--db")
+            .arg(path.path())
+            .arg("get")
+            .arg("--value-encoding")
+            .arg(encoding)
+            .arg(key);
+        cmd.assert().success().stdout(expected);
+    }
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("get")
+        .arg("--value-encoding")
+        .arg("utf8")
+        .arg(key);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Value is not valid UTF-8"));
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("get")
+        .arg("--value-encoding")
+        .arg("raw")
+        .arg(key);
+    cmd.assert().success().stdout(&b"\x00\xffhi"[..]);
+
+    let output_dir = tempdir()?;
+    let output = output_dir.path().join("value.bin");
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("get")
+        .arg("--output")
+        .arg(&output)
+        .arg(key);
+    cmd.assert().success().stdout("OK\n");
+    assert_eq!(std::fs::read(&output)?, b"\x00\xffhi");
+    Ok(())
+}
+
+#[test]
+fn key_and_value_codecs() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("batchput")
+        .arg("--key-codec")
+        .arg("u64be")
+        .arg("--value-codec")
+        .arg("varint")
+        .args(["256", "300", "1", "1", "65536", "7"]);
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--key-codec")
+        .arg("u64be")
+        .arg("--value-codec")
+        .arg("varint")
+        .arg("--from")
+        .arg("2");
+    cmd.assert().success().stdout("256 : 300\n65536 : 7\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("get")
+        .arg("--key-codec")
+        .arg("u64be")
+        .arg("--value-codec")
+        .arg("hex")
+        .arg("256");
+    cmd.assert().success().stdout("0xac02\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--key-codec")
+        .arg("base64url")
+        .arg("--value-codec")
+        .arg("escaped")
+        .arg("--to")
+        .arg("AAAAAAAAAQA");
+    cmd.assert().success().stdout("AAAAAAAAAAE : \\x01\n");
+
+    let key_file = tempdir()?;
+    let key_path = key_file.path().join("key");
+    std::fs::write(&key_path, b"\x00\x00\x00\x00\x00\x01\x00\x00")?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("delete")
+        .arg("--key-codec")
+        .arg("file")
+        .arg(&key_path);
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--key-codec")
+        .arg("u64be")
+        .arg("--value_hex");
+    cmd.assert().success().stdout("1 : 0x01\n256 : 0xac02\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("put")
+        .arg("--key-codec")
+        .arg("u32be")
+        .arg("4294967296")
+        .arg("value");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed: number too large"));
+    Ok(())
+}
+
+#[test]
+fn composite_key_format() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("batchput")
+        .arg("--key-format")
+        .arg("u8,u64be,str")
+        .args([
+            "1,42,alice",
+            "a",
+            "2,7,bob",
+            "b",
+            "2,300,carol",
+            "c",
+            "3,1,dave",
+            "d",
+        ]);
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--key-format")
+        .arg("u8,u64be,str")
+        .arg("--from")
+        .arg("2")
+        .arg("--to")
+        .arg("3");
+    cmd.assert()
+        .success()
+        .stdout("2,7,bob : b\n2,300,carol : c\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("get")
+        .arg("--key-format")
+        .arg("u8,u64be,str")
+        .arg("2,300,carol");
+    cmd.assert().success().stdout("c\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("deleterange")
+        .arg("--key-format")
+        .arg("u8,u64be,str")
+        .arg("2,8")
+        .arg("3");
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("put")
+        .arg("x")
+        .arg("e");
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("dump")
+        .arg("--key-format")
+        .arg("u8,u64be,str")
+        .arg("--output")
+        .arg("-");
+    cmd.assert()
+        .success()
+        .stdout("1,42,alice ==> a\n2,7,bob ==> b\n3,1,dave ==> d\n0x78 ==> e\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("get")
+        .arg("--key-format")
+        .arg("u8,u64be,str")
+        .arg("256,1,x");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Failed: Invalid value '256' of u8 key field",
+    ));
+    Ok(())
+}
+
+#[test]
+fn protobuf_values() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let descriptor = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/user.desc");
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("batchput")
+        .arg("--proto-descriptor")
+        .arg(descriptor)
+        .arg("--proto-message")
+        .arg("test.User")
+        .args([
+            "1",
+            r#"{"name": "alice", "id": 42}"#,
+            "2",
+            r#"{"name": "bob"}"#,
+        ]);
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("get")
+        .arg("--value_hex")
+        .arg("1");
+    cmd.assert().success().stdout("0x0a05616c696365102a\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("put")
+        .arg("--value_hex")
+        .arg("3")
+        .arg("ffff");
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--proto-descriptor")
+        .arg(descriptor)
+        .arg("--proto-message")
+        .arg("test.User");
+    cmd.assert()
+        .success()
+        .stdout("1 : {\"name\":\"alice\",\"id\":\"42\"}\n2 : {\"name\":\"bob\"}\n3 : 0xffff\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("put")
+        .arg("--proto-descriptor")
+        .arg(descriptor)
+        .arg("--proto-message")
+        .arg("test.User")
+        .arg("4")
+        .arg(r#"{"age": 1}"#);
+    cmd.assert().failure();
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("get")
+        .arg("--proto-descriptor")
+        .arg(descriptor)
+        .arg("--proto-message")
+        .arg("test.Group")
+        .arg("1");
+    cmd.assert().failure().stderr(format!(
+        "Failed: Message test.Group is not found in {}\n",
+        descriptor
+    ));
+    Ok(())
+}
+
+#[test]
+fn structured_values() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    for (key, codec) in [("1", "msgpack"), ("2", "cbor"), ("3", "json")] {
+        let mut cmd = Command::cargo_bin("rdbrowser")?;
+        cmd.arg("--create_if_missing")
+            .arg("--db")
+            .arg(path.path())
+            .arg("put")
+            .arg("--value-codec")
+            .arg(codec)
+            .arg(key)
+            .arg(r#"{"id": 1, "tags": ["a"]}"#);
+        cmd.assert().success().stdout("OK\n");
+    }
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("get")
+        .arg("--value_hex")
+        .arg("1");
+    cmd.assert()
+        .success()
+        .stdout("0x82a2696401a47461677391a161\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("get")
+        .arg("--value-decoder")
+        .arg("cbor")
+        .arg("2");
+    cmd.assert()
+        .success()
+        .stdout("{\"id\":1,\"tags\":[\"a\"]}\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("dump")
+        .arg("--value-codec")
+        .arg("msgpack")
+        .arg("--output")
+        .arg("-");
+    cmd.assert().success().stdout(
+        "1 ==> {\"id\":1,\"tags\":[\"a\"]}\n2 ==> 0xa2626964016474616773816161\n3 ==> 0x7b226964223a312c2274616773223a5b2261225d7d\n",
+    );
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("put")
+        .arg("--bincode-schema")
+        .arg("id:u32,name:string")
+        .arg("4")
+        .arg(r#"{"id": 7, "name": "bob"}"#);
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--bincode-schema")
+        .arg("id:u32,name:string")
+        .arg("--from")
+        .arg("3");
+    cmd.assert().success().stdout(
+        "3 : 0x7b226964223a312c2274616773223a5b2261225d7d\n4 : {\"id\":7,\"name\":\"bob\"}\n",
+    );
+    Ok(())
+}
+
+#[test]
+fn schema_config() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let schema_dir = tempdir()?;
+    let schema = schema_dir.path().join("schema.toml");
+    std::fs::write(
+        &schema,
+        r#"
+[[rule]]
+prefix_hex = "0x01"
+key_format = "u8,u32be"
+value_codec = "json"
+
+[[rule]]
+prefix = "cfg:"
+value_codec = "hex"
+"#,
+    )?;
+    for (key, value) in [
+        ("1,7", r#"{"a": 1}"#),
+        ("cfg:x", "0x6869"),
+        ("plain", "text"),
+    ] {
+        let mut cmd = Command::cargo_bin("rdbrowser")?;
+        cmd.arg("--create_if_missing")
+            .arg("--db")
+            .arg(path.path())
+            .arg("--schema")
+            .arg(&schema)
+            .arg("put")
+            .arg(key)
+            .arg(value);
+        cmd.assert().success().stdout("OK\n");
+    }
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("--schema")
+        .arg(&schema)
+        .arg("scan");
+    cmd.assert()
+        .success()
+        .stdout("1,7 : {\"a\":1}\ncfg:x : 0x6869\nplain : text\n");
+
+    // Without a schema the keys and values are shown as stored
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db").arg(path.path()).arg("get").arg("cfg:x");
+    cmd.assert().success().stdout("hi\n");
+
+    // The schema in the DB directory is picked up automatically
+    std::fs::copy(&schema, path.path().join(".rdbrowser.toml"))?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db").arg(path.path()).arg("get").arg("1,7");
+    cmd.assert().success().stdout("{\"a\":1}\n");
+
+    // Codecs given on the command line override the schema
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("get")
+        .arg("--key_hex")
+        .arg("--value-codec")
+        .arg("base64")
+        .arg("0x0100000007");
+    cmd.assert().success().stdout("eyJhIjoxfQ==\n");
+
+    std::fs::write(&schema, "[[rule]]\nvalue_codec = \"utf16\"\n")?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("--schema")
+        .arg(&schema)
+        .arg("scan");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid rule 1 of schema"));
+    Ok(())
+}
+
+#[test]
+fn prefix_scan() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("batchput")
+        .args(["a1", "1", "ab", "2", "ab1", "3", "ac", "4", "b", "5"]);
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--prefix")
+        .arg("ab");
+    cmd.assert().success().stdout("ab : 2\nab1 : 3\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--prefix")
+        .arg("a")
+        .arg("--prefix-length")
+        .arg("1");
+    cmd.assert()
+        .success()
+        .stdout("a1 : 1\nab : 2\nab1 : 3\nac : 4\n");
+
+    // Reverse scans seek before the end of the prefix, which has another extracted prefix
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--prefix")
+        .arg("a")
+        .arg("--prefix-length")
+        .arg("1")
+        .arg("--reverse");
+    cmd.assert()
+        .success()
+        .stdout("ac : 4\nab1 : 3\nab : 2\na1 : 1\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--prefix")
+        .arg("ab")
+        .arg("--prefix-length")
+        .arg("2")
+        .arg("--last")
+        .arg("1");
+    cmd.assert().success().stdout("ab1 : 3\n");
+
+    // The extracted prefix may not be longer than the scanned one
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--prefix")
+        .arg("a")
+        .arg("--prefix-length")
+        .arg("2");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "shorter than the prefix extractor length",
+    ));
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--prefix")
+        .arg("a")
+        .arg("--from")
+        .arg("a1");
+    cmd.assert().failure();
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("dump")
+        .arg("--prefix")
+        .arg("0x6162")
+        .arg("--key_hex")
+        .arg("--output")
+        .arg("-");
+    cmd.assert()
+        .success()
+        .stdout("0x6162 ==> 2\n0x616231 ==> 3\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("deleterange")
+        .arg("--prefix")
+        .arg("ab");
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db").arg(path.path()).arg("scan");
+    cmd.assert().success().stdout("a1 : 1\nac : 4\nb : 5\n");
+    Ok(())
+}
+
+#[test]
+fn range_bounds() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("batchput")
+        .args(["ab", "1", "abc", "2", "abcd", "3", "abd", "4"]);
+    cmd.assert().success().stdout("OK\n");
+
+    // Keys longer than the end bound are past it
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--to")
+        .arg("abc");
+    cmd.assert().success().stdout("ab : 1\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--from")
+        .arg("abc")
+        .arg("--to")
+        .arg("abc")
+        .arg("--inclusive");
+    cmd.assert().success().stdout("abc : 2\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("dump")
+        .arg("--from")
+        .arg("abc")
+        .arg("--to")
+        .arg("abd")
+        .arg("--output")
+        .arg("-");
+    cmd.assert().success().stdout("abc ==> 2\nabcd ==> 3\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--inclusive");
+    cmd.assert().failure();
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("deleterange")
+        .arg("--inclusive")
+        .arg("abc")
+        .arg("abd");
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db").arg(path.path()).arg("scan");
+    cmd.assert().success().stdout("ab : 1\n");
+    Ok(())
+}
+
+#[test]
+fn reverse_scan_and_seek_for_prev() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("batchput")
+        .args(["t1", "a", "t2", "b", "t3", "c", "u", "d"]);
+    cmd.assert().success().stdout("OK\n");
+
+    let scan = |args: &[&str]| -> Result<Command, Box<dyn std::error::Error>> {
+        let mut cmd = Command::cargo_bin("rdbrowser")?;
+        cmd.arg("--db").arg(path.path()).arg("scan").args(args);
+        Ok(cmd)
+    };
+    scan(&["--reverse"])?
+        .assert()
+        .success()
+        .stdout("u : d\nt3 : c\nt2 : b\nt1 : a\n");
+    scan(&["--reverse", "--to", "t3"])?
+        .assert()
+        .success()
+        .stdout("t2 : b\nt1 : a\n");
+    scan(&["--prefix", "t", "--last", "2"])?
+        .assert()
+        .success()
+        .stdout("t2 : b\nt3 : c\n");
+    scan(&["--prefix", "t", "--last", "2", "--reverse"])?
+        .assert()
+        .success()
+        .stdout("t3 : c\nt2 : b\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("get")
+        .arg("--seek-for-prev")
+        .arg("t25");
+    cmd.assert().success().stdout("t2 : b\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("get")
+        .arg("--seek-for-prev")
+        .arg("t");
+    cmd.assert().success().stderr("Not Found\n");
+    Ok(())
+}
+
+#[test]
+fn scan_pages() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("batchput")
+        .args(["k1", "1", "k2", "2", "k3", "3", "k4", "4", "k5", "5"]);
+    cmd.assert().success().stdout("OK\n");
+
+    let page = |args: &[&str]| -> Result<(String, Option<String>), Box<dyn std::error::Error>> {
+        let mut cmd = Command::cargo_bin("rdbrowser")?;
+        let output = cmd
+            .arg("--db")
+            .arg(path.path())
+            .arg("scan")
+            .args(args)
+            .output()?;
+        assert!(output.status.success());
+        let cursor = String::from_utf8(output.stderr)?
+            .trim()
+            .strip_prefix("Next page: --cursor ")
+            .map(String::from);
+        Ok((String::from_utf8(output.stdout)?, cursor))
+    };
+
+    let (records, cursor) = page(&["--skip", "1", "--limit", "2"])?;
+    assert_eq!(records, "k2 : 2\nk3 : 3\n");
+    let cursor = cursor.unwrap();
+    let (records, next) = page(&["--limit", "2", "--cursor", &cursor])?;
+    assert_eq!(records, "k4 : 4\nk5 : 5\n");
+    // The last page is not followed by a cursor
+    assert_eq!(next, None);
+    // Pages continue with the same flags, the skipped keys only precede the first one
+    let (records, _) = page(&["--skip", "1", "--limit", "2", "--cursor", &cursor])?;
+    assert_eq!(records, "k4 : 4\nk5 : 5\n");
+
+    let (records, cursor) = page(&["--reverse", "--limit", "3"])?;
+    assert_eq!(records, "k5 : 5\nk4 : 4\nk3 : 3\n");
+    let cursor = cursor.unwrap();
+    let (records, _) = page(&["--reverse", "--limit", "3", "--cursor", &cursor])?;
+    assert_eq!(records, "k2 : 2\nk1 : 1\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--cursor")
+        .arg(&cursor);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("other direction"));
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--limit")
+        .arg("0");
+    cmd.assert()
+        .failure()
+        .stderr("Failed: Limit must be positive\n");
+    Ok(())
+}
+
+#[test]
+fn filtered_scan() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("batchput")
+        .arg("--value_hex")
+        .args([
+            "user:1", "0x616263", "user:2", "0x00ff", "user:x", "0x00ff", "order:1", "0x61",
+        ]);
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--value_hex")
+        .arg("--key-regex")
+        .arg("^user:[0-9]+$")
+        .arg("--value-contains")
+        .arg("0xff");
+    cmd.assert()
+        .success()
+        .stdout("user:2 : 0x00ff\n")
+        .stderr("Examined 4 keys, 1 matched\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("dump")
+        .arg("--prefix")
+        .arg("user:")
+        .arg("--min-value-size")
+        .arg("3")
+        .arg("--output")
+        .arg("-");
+    cmd.assert()
+        .success()
+        .stdout("user:1 ==> abc\n")
+        .stderr("Examined 3 keys, 1 matched\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--value-regex")
+        .arg("(");
+    cmd.assert().failure();
+    Ok(())
+}
+
+#[test]
+fn query_scan() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("batchput")
+        .arg("--key-format")
+        .arg("tenant:u8,user_id:u64be")
+        .args([
+            "1,999",
+            r#"{"status":"active","name":"ann"}"#,
+            "1,1001",
+            r#"{"status":"active","name":"bob"}"#,
+            "1,1002",
+            r#"{"status":"banned","name":"eve"}"#,
+        ]);
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--key-format")
+        .arg("tenant:u8,user_id:u64be")
+        .arg("--value-codec")
+        .arg("json")
+        .arg("--where")
+        .arg(r#"key.user_id > 1000 && value.status == "active""#)
+        .arg("--select")
+        .arg("value.name,key.user_id");
+    cmd.assert()
+        .success()
+        .stdout("1,1001 : {\"value.name\":\"bob\",\"key.user_id\":1001}\n")
+        .stderr("Examined 3 keys, 1 matched\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("scan")
+        .arg("--where")
+        .arg("key.user_id >");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid query at position 13"));
+    Ok(())
+}
+
+#[test]
+fn count_keys() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("batchput")
+        .args(["user:1", "abc", "user:22", "de", "order:1", "f"]);
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("count")
+        .arg("--prefix")
+        .arg("user:");
+    cmd.assert()
+        .success()
+        .stdout("keys : 2\nkey bytes : 13\nvalue bytes : 5\n");
+
+    // Ranges smaller than the sample are counted exactly
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("count")
+        .arg("--estimate")
+        .arg("--to")
+        .arg("user:1")
+        .arg("--inclusive");
+    cmd.assert()
+        .success()
+        .stdout("keys : 2\nkey bytes : 13\nvalue bytes : 4\n");
+    Ok(())
+}
+
+#[test]
+fn estimated_count() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let records: Vec<String> = (0..1500)
+        .flat_map(|i| [format!("k{:04}", i), i.to_string()])
+        .collect();
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("batchput")
+        .args(&records);
+    cmd.assert().success().stdout("OK\n");
+
+    // The range is larger than the sample
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("count")
+        .arg("--estimate");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("estimated keys : "))
+        .stdout(predicate::str::contains("estimated key bytes : "))
+        .stdout(predicate::str::contains("estimated value bytes : "))
+        .stderr("");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db").arg(path.path()).arg("createcf").arg("cf1");
+    cmd.assert().success().stdout("OK\n");
+
+    // SST files of the column families can not be told apart
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("count")
+        .arg("--estimate")
+        .arg("--prefix")
+        .arg("k0");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("estimated keys : "))
+        .stderr(predicate::str::starts_with("Warning: "));
+    Ok(())
+}
+
+#[test]
+fn key_space_stats() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("batchput")
+        .args(["user:1", "abc", "user:22", "de", "order:1", "f"]);
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("stats")
+        .arg("--prefix-delimiter")
+        .arg(":")
+        .arg("--top")
+        .arg("2");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Keys: 3\nBytes: 20 of keys, 6 of values\nKey length: min 6, max 7, mean 6.7\n",
+        ))
+        .stdout(predicate::str::contains(
+            "Top prefixes of 1 segment:\n  user: : 2 keys, 18 bytes, 69.23%\n  order: : 1 key, 8 bytes, 30.77%\n",
+        ))
+        .stdout(predicate::str::contains(
+            "Largest values:\n  user:1 : 3 bytes\n  user:22 : 2 bytes\n",
+        ));
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("--output-format")
+        .arg("jsonl")
+        .arg("stats")
+        .arg("--prefix")
+        .arg("user:")
+        .arg("--sample")
+        .arg("2");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let report: serde_json::Value = serde_json::from_slice(&output)?;
+    assert_eq!(report["examined"], 2);
+    assert_eq!(report["keys"], 1);
+    assert_eq!(report["value_size"]["max"], 3);
+    assert_eq!(report["prefixes"][0]["top"][0]["prefix"], "u");
+    Ok(())
+}
+
+#[test]
+fn db_properties() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("put")
+        .arg("key")
+        .arg("value");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db").arg(path.path()).arg("properties");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("Column family: default\n"))
+        .stdout(predicate::str::contains("rocksdb.num-live-versions : "))
+        .stdout(predicate::str::contains("rocksdb.stats :\n"));
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("properties")
+        .arg("--json")
+        .arg("--property")
+        .arg("rocksdb.estimate-num-keys")
+        .arg("--property")
+        .arg("rocksdb.levelstats")
+        .arg("--property")
+        .arg("rocksdb.unknown");
+    let output = cmd.assert().success().get_output().stdout.clone();
+    let properties: serde_json::Value = serde_json::from_slice(&output)?;
+    assert_eq!(properties["default"]["rocksdb.estimate-num-keys"], 1);
+    assert!(properties["default"]["rocksdb.levelstats"].is_string());
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("--output-format")
+        .arg("jsonl")
+        .arg("properties")
+        .arg("--property")
+        .arg("rocksdb.estimate-num-keys");
+    cmd.assert()
+        .success()
+        .stdout("{\"default\":{\"rocksdb.estimate-num-keys\":1}}\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("--output-format")
+        .arg("csv")
+        .arg("properties");
+    cmd.assert()
+        .failure()
+        .stderr("Failed: Properties can not be printed as csv\n");
+    assert_eq!(
+        properties["default"]["rocksdb.unknown"],
+        serde_json::Value::Null
+    );
+    Ok(())
+}
+
+#[test]
+fn binary_load_is_verified_before_writing() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("batchput")
+        .args(["a", "1", "b", "2", "c", "3"]);
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("createcf")
+        .arg("empty");
+    cmd.assert().success().stdout("OK\n");
+
+    let dump_dir = tempdir()?;
+    let dump_path = dump_dir.path().join("dump.bin");
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("dump")
+        .arg("--all-column-families")
+        .arg("--format")
+        .arg("binary")
+        .arg("--output")
+        .arg(&dump_path);
+    cmd.assert().success();
+
+    // Empty column families are restored too
+    let restored = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(restored.path())
+        .arg("load")
+        .arg("--format")
+        .arg("binary")
+        .arg("--input")
+        .arg(&dump_path);
+    cmd.assert().success().stdout("OK\n");
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db").arg(restored.path()).arg("listcf");
+    cmd.assert().success().stdout("default\nempty\n");
+
+    // A flipped byte in the first record is only noticed at the trailer, the batches before
+    // it must not be written
+    let mut data = std::fs::read(&dump_path)?;
+    // Key length, key and value length of the first record
+    let position = data
+        .windows(6)
+        .position(|window| window == b"\x01\x00\x00\x00a\x01")
+        .unwrap();
+    data[position + 4] = b'x';
+    std::fs::write(&dump_path, data)?;
+    let restored = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(restored.path())
+        .arg("load")
+        .arg("--format")
+        .arg("binary")
+        .arg("--batch-size")
+        .arg("1")
+        .arg("--input")
+        .arg(&dump_path);
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Corrupted dump: checksum mismatch",
+    ));
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db").arg(restored.path()).arg("scan");
+    cmd.assert().success().stdout("");
+    Ok(())
+}
+
+#[test]
+fn schema_dump_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let path = tempdir()?;
+    let schema = path.path().join(".rdbrowser.toml");
+    std::fs::write(
+        &schema,
+        "[[rule]]\nprefix = \"user:\"\nvalue_codec = \"json\"\n",
+    )?;
+    // The second value is not JSON, so it is dumped in hex
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(path.path())
+        .arg("batchput")
+        .arg("--value_hex")
+        .args(["user:1", "0x7b2261223a317d", "user:2", "0x00ff"]);
+    cmd.assert().success().stdout("OK\n");
+
+    let dump_dir = tempdir()?;
+    let dump_path = dump_dir.path().join("dump.txt");
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(path.path())
+        .arg("dump")
+        .arg("--output")
+        .arg(&dump_path);
+    cmd.assert().success();
+    assert_eq!(
+        std::fs::read_to_string(&dump_path)?,
+        "user:1 ==> {\"a\":1}\nuser:2 ==> 0x00ff\n"
+    );
+
+    let restored = tempdir()?;
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--create_if_missing")
+        .arg("--db")
+        .arg(restored.path())
+        .arg("--schema")
+        .arg(&schema)
+        .arg("load")
+        .arg("--input")
+        .arg(&dump_path);
+    cmd.assert().success().stdout("OK\n");
+
+    let mut cmd = Command::cargo_bin("rdbrowser")?;
+    cmd.arg("--db")
+        .arg(restored.path())
+        .arg("scan")
+        .arg("--hex");
+    cmd.assert()
+        .success()
+        .stdout("0x757365723a31 : 0x7b2261223a317d\n0x757365723a32 : 0x00ff\n");
+    Ok(())
+}